@@ -1,6 +1,6 @@
 use proc_macro::{self, TokenStream};
 use quote::quote;
-use syn::{braced, parenthesized, parse::{Parse, ParseStream}, parse_macro_input, token::Paren, Attribute, Error, Ident, LitStr, Token, Type, Visibility};
+use syn::{braced, parenthesized, parse::{Parse, ParseStream}, parse_macro_input, token::Paren, Attribute, Error, Expr, Ident, LitInt, LitStr, Token, Type, Visibility};
 
 enum ObjectParsed {
     EnumParsed(EnumParsed),
@@ -21,37 +21,137 @@ struct StructParsed {
 
 #[derive(Debug)]
 struct Var {
+    ident: Ident,
     name: String,
-    ty: Option<Ty>
+    ty: Option<Ty>,
+    limits: Limits
 }
 
 #[derive(Debug)]
 struct Ty {
     ident: Ident,
-    name: String
+    name: String,
+    // Whether this was a Vec of the type
+    list: bool,
+    // Whether this was an Option of the type
+    optional: bool
 }
 
 #[derive(Debug)]
 struct Field {
     name: String,
-    ty: String,
+    ty: Ty,
+    limits: Limits
+}
+
+// What a field's #[listfields(...)] attribute allows. For lists the limits
+// are on the length, otherwise on the value.
+#[derive(Debug, Default)]
+struct Limits {
+    min: Option<i64>,
+    max: Option<i64>,
+    // At most one per turn, so no more than the game's number of turns
+    per_turn: bool
+}
+
+impl Limits {
+    // Reads every #[listfields(...)] in `attrs`, other attributes are left alone
+    fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut limits = Limits::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("listfields")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("min") {
+                    let min: LitInt = meta.value()?.parse()?;
+                    limits.min = Some(min.base10_parse()?);
+                }
+                else if meta.path.is_ident("max") {
+                    let max: LitInt = meta.value()?.parse()?;
+                    limits.max = Some(max.base10_parse()?);
+                }
+                else if meta.path.is_ident("per_turn") {
+                    limits.per_turn = true;
+                }
+                else {
+                    return Err(meta.error("expected `min`, `max` or `per_turn`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(limits)
+    }
+
+    // Code that returns an Err from validate() if `value` (an Option<i64>) is
+    // outside the limits. `name` is what to call it in the error.
+    fn checks(&self, name: &str, value: proc_macro2::TokenStream, list: bool) -> proc_macro2::TokenStream {
+        let (fewer, more) = if list {
+            ("can't have fewer than {} entries, got {}", "can't have more than {} entries, got {}")
+        } else {
+            ("can't be less than {}, got {}", "can't be more than {}, got {}")
+        };
+
+        let min_check = self.min.map(|min| {
+            let message = format!("{name} {fewer}");
+            quote! {
+                if value < #min {
+                    return Err(format!(#message, #min, value));
+                }
+            }
+        });
+        let max_check = self.max.map(|max| {
+            let message = format!("{name} {more}");
+            quote! {
+                if value > #max {
+                    return Err(format!(#message, #max, value));
+                }
+            }
+        });
+        let per_turn_check = self.per_turn.then(|| {
+            let message = format!("{name} can't be more than the number of turns ({{}}), got {{}}");
+            quote! {
+                if value > turns as i64 {
+                    return Err(format!(#message, turns, value));
+                }
+            }
+        });
+
+        if min_check.is_none() && max_check.is_none() && per_turn_check.is_none() {
+            return quote! {};
+        }
+
+        quote! {
+            if let Some(value) = #value {
+                #min_check
+                #max_check
+                #per_turn_check
+            }
+        }
+    }
 }
 
+// Types that don't implement ListFields themselves, so fields of these
+// types don't get any type_data
+const PRIMITIVES: [&str; 13] = ["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64", "bool", "String", "DateTime"];
+
 impl Parse for Ty {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let ty: Type = input.parse()?;
         match ty {
             Type::Path(path_type) => {
                 let segment = &path_type.path.segments[0];
+                let wrapper = segment.ident == "Vec" || segment.ident == "Option";
                 match &segment.arguments {
-                    syn::PathArguments::AngleBracketed(angle_bracket_args) => {
+                    syn::PathArguments::AngleBracketed(angle_bracket_args) if wrapper => {
                         match &angle_bracket_args.args[0] {
                             syn::GenericArgument::Type(ty) => {
                                 match ty {
                                     Type::Path(path_type) => {
                                         Ok(Ty{
                                             ident: path_type.path.segments[0].ident.clone(),
-                                            name: path_type.path.segments[0].ident.to_string()
+                                            name: path_type.path.segments[0].ident.to_string(),
+                                            list: segment.ident == "Vec",
+                                            optional: segment.ident == "Option"
                                         })
                                     },
                                     _ => {
@@ -64,10 +164,14 @@ impl Parse for Ty {
                             }
                         }
                     },
-                    syn::PathArguments::None => {
+                    // Other generic types, like DateTime<Utc>, are described by
+                    // their own name
+                    syn::PathArguments::None | syn::PathArguments::AngleBracketed(_) => {
                         Ok(Ty{
                             ident: segment.ident.clone(),
-                            name: segment.ident.to_string()
+                            name: segment.ident.to_string(),
+                            list: false,
+                            optional: false
                         })
                     },
                     _ => {
@@ -87,16 +191,25 @@ impl Parse for Var {
         let lookahead = input.lookahead1();
 
         let mut name = None;
+        let mut limits = Limits::default();
         if lookahead.peek(Token![#]){
-            let attr = Attribute::parse_outer(input)?;
-
-            Attribute::parse_nested_meta(&attr[0], |meta| {
-                let value = meta.value()?;
-                let rename: LitStr = value.parse()?;
+            let attrs = Attribute::parse_outer(input)?;
+
+            // The name is whatever serde calls the variant
+            for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        let rename: LitStr = meta.value()?.parse()?;
+                        name = Some(rename.value());
+                    }
+                    else if meta.input.peek(Token![=]) {
+                        let _: Expr = meta.value()?.parse()?;
+                    }
+                    Ok(())
+                })?;
+            }
 
-                name = Some(rename.value());
-                Ok(())
-            })?;
+            limits = Limits::from_attrs(&attrs)?;
         }
 
         let ident: Ident = input.parse()?;
@@ -111,13 +224,17 @@ impl Parse for Var {
             let ty: Ty = content.parse()?;
 
             Ok(Var{
+                ident,
                 name: name.unwrap(),
-                ty: Some(ty)
+                ty: Some(ty),
+                limits
             })
         } else {
             Ok(Var{
+                ident,
                 name: name.unwrap(),
-                ty: None
+                ty: None,
+                limits
             })
         }
     }
@@ -125,6 +242,9 @@ impl Parse for Var {
 
 impl Parse for Field {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = Attribute::parse_outer(input)?;
+        let limits = Limits::from_attrs(&attrs)?;
+
         let lookahead = input.lookahead1();
         if lookahead.peek(Token![pub]) {
             let _ = input.parse::<Visibility>();
@@ -136,7 +256,8 @@ impl Parse for Field {
 
         return Ok(Field{
             name: name.to_string(),
-            ty: ty.name
+            ty,
+            limits
         })
     }
 }
@@ -201,7 +322,7 @@ impl Parse for ObjectParsed {
     }
 }
 
-#[proc_macro_derive(ListFields)]
+#[proc_macro_derive(ListFields, attributes(listfields))]
 pub fn list_fields_macro(input: TokenStream) -> TokenStream {
     let object_parsed: ObjectParsed = parse_macro_input!(input as ObjectParsed);
     match object_parsed {
@@ -209,9 +330,14 @@ pub fn list_fields_macro(input: TokenStream) -> TokenStream {
             let name_str = name.to_string();
 
             let mut variant_tokens = Vec::new();
+            let mut validate_arms = Vec::new();
 
             for variant in variants {
                 let v_name = variant.name;
+                let v_ident = variant.ident;
+                let v_min = option_tokens(variant.limits.min);
+                let v_max = option_tokens(variant.limits.max);
+
                 if let Some(ty) = variant.ty {
                     let v_ty_name = ty.name;
                     let v_ty_ident = ty.ident;
@@ -221,20 +347,52 @@ pub fn list_fields_macro(input: TokenStream) -> TokenStream {
                             Variant {
                                 name: #v_name.to_string(),
                                 ty: Some(#v_ty_name.to_string()),
-                                type_data: Some(#v_ty_ident::list_fields())
+                                type_data: Some(#v_ty_ident::list_fields()),
+                                min: #v_min,
+                                max: #v_max
                             }
                         }
                     );
+
+                    if ty.list {
+                        let checks = variant.limits.checks(&v_name, quote! { Some(data.len() as i64) }, true);
+                        let message = format!("{v_name} #{{}}: {{}}");
+                        validate_arms.push(
+                            quote! {
+                                Self::#v_ident(data) => {
+                                    #checks
+                                    for (i, item) in data.iter().enumerate() {
+                                        item.validate(turns).map_err(|err| format!(#message, i + 1, err))?;
+                                    }
+                                    Ok(())
+                                }
+                            }
+                        );
+                    } else {
+                        validate_arms.push(
+                            quote! {
+                                Self::#v_ident(data) => data.validate(turns)
+                            }
+                        );
+                    }
                 } else {
                     variant_tokens.push(
                         quote! {
                             Variant {
                                 name: #v_name.to_string(),
                                 ty: None,
-                                type_data: None
+                                type_data: None,
+                                min: #v_min,
+                                max: #v_max
                             }
                         }
                     );
+
+                    validate_arms.push(
+                        quote! {
+                            Self::#v_ident => Ok(())
+                        }
+                    );
                 }
             }
 
@@ -249,6 +407,13 @@ pub fn list_fields_macro(input: TokenStream) -> TokenStream {
                             variants: v,
                         })
                     }
+
+                    #[allow(unused_variables)]
+                    fn validate(&self, turns: i32) -> Result<(), String> {
+                        match self {
+                            #( #validate_arms, )*
+                        }
+                    }
                 }
             };
 
@@ -256,18 +421,54 @@ pub fn list_fields_macro(input: TokenStream) -> TokenStream {
         },
         ObjectParsed::StructParsed( StructParsed{ name, fields } ) => {
             let mut field_tokens = Vec::new();
+            let mut validate_tokens = Vec::new();
 
             for field in fields {
                 let f_name = field.name;
-                let f_ty = field.ty;
+                let f_ident = Ident::new(&f_name, proc_macro2::Span::call_site());
+                let f_ty_name = field.ty.name;
+                let f_ty_ident = field.ty.ident;
+                let f_list = field.ty.list;
+                let f_min = option_tokens(field.limits.min);
+                let f_max = option_tokens(field.limits.max);
+                let f_per_turn = field.limits.per_turn;
+                let f_optional = field.ty.optional;
+                let primitive = PRIMITIVES.contains(&f_ty_name.as_str());
+
+                let f_type_data = if primitive {
+                    quote! { None }
+                } else {
+                    quote! { Some(#f_ty_ident::list_fields()) }
+                };
+
                 field_tokens.push(
                     quote! {
                         Field {
                             name: #f_name.to_string(),
-                            ty: #f_ty.to_string()
+                            ty: #f_ty_name.to_string(),
+                            list: #f_list,
+                            optional: #f_optional,
+                            type_data: #f_type_data,
+                            min: #f_min,
+                            max: #f_max,
+                            per_turn: #f_per_turn
                         }
                     }
-                )
+                );
+
+                let value = match (field.ty.optional, f_list) {
+                    (false, false) => quote! { Some(self.#f_ident as i64) },
+                    (false, true) => quote! { Some(self.#f_ident.len() as i64) },
+                    (true, false) => quote! { self.#f_ident.map(|value| value as i64) },
+                    (true, true) => quote! { self.#f_ident.as_ref().map(|value| value.len() as i64) }
+                };
+                validate_tokens.push(field.limits.checks(&f_name, value, f_list));
+
+                // Fields of our own types, like the player data in a game,
+                // get their own limits checked too
+                if !primitive && !f_list && !field.ty.optional {
+                    validate_tokens.push(quote! { self.#f_ident.validate(turns)?; });
+                }
             }
 
             let output = quote! {
@@ -279,6 +480,12 @@ pub fn list_fields_macro(input: TokenStream) -> TokenStream {
 
                         ObjectData::Fields(v)
                     }
+
+                    #[allow(unused_variables)]
+                    fn validate(&self, turns: i32) -> Result<(), String> {
+                        #( #validate_tokens )*
+                        Ok(())
+                    }
                 }
             };
 
@@ -286,3 +493,44 @@ pub fn list_fields_macro(input: TokenStream) -> TokenStream {
         }
     }
 }
+
+// Some(n) or None as tokens
+fn option_tokens(value: Option<i64>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None }
+    }
+}
+
+/// Derives `EntryTable` for a struct holding one player's stats for a game.
+/// Every field is a column of the same name in the struct's table, which is
+/// the struct's name followed by "Entries", like MarioParty9Entries.
+#[proc_macro_derive(EntryTable)]
+pub fn entry_table_macro(input: TokenStream) -> TokenStream {
+    let object_parsed: ObjectParsed = parse_macro_input!(input as ObjectParsed);
+    match object_parsed {
+        ObjectParsed::StructParsed( StructParsed{ name, fields } ) => {
+            let table = format!("{name}Entries");
+            let columns: Vec<String> = fields.iter().map(|field| field.name.clone()).collect();
+            let idents: Vec<Ident> = fields.iter()
+                .map(|field| Ident::new(&field.name, proc_macro2::Span::call_site()))
+                .collect();
+
+            let output = quote! {
+                impl EntryTable for #name {
+                    const TABLE: &'static str = #table;
+                    const COLUMNS: &'static [&'static str] = &[ #( #columns ),* ];
+
+                    fn bind_all<'q>(self, query: sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments>) -> sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments> {
+                        query #( .bind(self.#idents) )*
+                    }
+                }
+            };
+
+            output.into()
+        },
+        ObjectParsed::EnumParsed( EnumParsed{ name, variants: _ } ) => {
+            Error::new(name.span(), "EntryTable can only be derived for structs").to_compile_error().into()
+        }
+    }
+}
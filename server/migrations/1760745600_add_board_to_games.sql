@@ -0,0 +1,3 @@
+-- Games submitted before boards were stored don't have one, so this has to
+-- stay nullable. New games are checked against the board enums in boards.rs
+ALTER TABLE Games ADD COLUMN board TEXT;
@@ -0,0 +1,23 @@
+-- Which game a row is for used to only be knowable by looking for its
+-- entries in every *Entries table, so store it directly
+ALTER TABLE Games ADD COLUMN game TEXT;
+
+UPDATE Games SET game = 'Mario Party' WHERE id IN (SELECT game_id FROM MarioPartyEntries);
+UPDATE Games SET game = 'Mario Party 2' WHERE id IN (SELECT game_id FROM MarioParty2Entries);
+UPDATE Games SET game = 'Mario Party 3' WHERE id IN (SELECT game_id FROM MarioParty3Entries);
+UPDATE Games SET game = 'Mario Party 4' WHERE id IN (SELECT game_id FROM MarioParty4Entries);
+UPDATE Games SET game = 'Mario Party 5' WHERE id IN (SELECT game_id FROM MarioParty5Entries);
+UPDATE Games SET game = 'Mario Party 6' WHERE id IN (SELECT game_id FROM MarioParty6Entries);
+UPDATE Games SET game = 'Mario Party 7' WHERE id IN (SELECT game_id FROM MarioParty7Entries);
+UPDATE Games SET game = 'Mario Party 8' WHERE id IN (SELECT game_id FROM MarioParty8Entries);
+UPDATE Games SET game = 'Mario Party 9' WHERE id IN (SELECT game_id FROM MarioParty9Entries);
+UPDATE Games SET game = 'Mario Party 10' WHERE id IN (SELECT game_id FROM MarioParty10Entries);
+UPDATE Games SET game = 'Mario Party DS' WHERE id IN (SELECT game_id FROM MarioPartyDSEntries);
+UPDATE Games SET game = 'Mario Party: Island Tour' WHERE id IN (SELECT game_id FROM MarioPartyIslandTourEntries);
+UPDATE Games SET game = 'Mario Party: Star Rush' WHERE id IN (SELECT game_id FROM MarioPartyStarRushEntries);
+UPDATE Games SET game = 'Mario Party: The Top 100' WHERE id IN (SELECT game_id FROM MarioPartyTop100Entries);
+UPDATE Games SET game = 'Super Mario Party' WHERE id IN (SELECT game_id FROM SuperMarioPartyEntries);
+UPDATE Games SET game = 'Mario Party Superstars' WHERE id IN (SELECT game_id FROM MarioPartySuperstarsEntries);
+UPDATE Games SET game = 'Mario Party Jamboree' WHERE id IN (SELECT game_id FROM MarioPartyJamboreeEntries);
+
+CREATE INDEX games_user_id_date_idx ON Games (user_id, date);
@@ -0,0 +1,68 @@
+-- Deleting a game should take its entries with it
+ALTER TABLE MarioPartyEntries
+    DROP CONSTRAINT mariopartyentries_game_id_fkey,
+    ADD CONSTRAINT mariopartyentries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty2Entries
+    DROP CONSTRAINT marioparty2entries_game_id_fkey,
+    ADD CONSTRAINT marioparty2entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty3Entries
+    DROP CONSTRAINT marioparty3entries_game_id_fkey,
+    ADD CONSTRAINT marioparty3entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty4Entries
+    DROP CONSTRAINT marioparty4entries_game_id_fkey,
+    ADD CONSTRAINT marioparty4entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty5Entries
+    DROP CONSTRAINT marioparty5entries_game_id_fkey,
+    ADD CONSTRAINT marioparty5entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty6Entries
+    DROP CONSTRAINT marioparty6entries_game_id_fkey,
+    ADD CONSTRAINT marioparty6entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty7Entries
+    DROP CONSTRAINT marioparty7entries_game_id_fkey,
+    ADD CONSTRAINT marioparty7entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty8Entries
+    DROP CONSTRAINT marioparty8entries_game_id_fkey,
+    ADD CONSTRAINT marioparty8entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty9Entries
+    DROP CONSTRAINT marioparty9entries_game_id_fkey,
+    ADD CONSTRAINT marioparty9entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioParty10Entries
+    DROP CONSTRAINT marioparty10entries_game_id_fkey,
+    ADD CONSTRAINT marioparty10entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioPartyDSEntries
+    DROP CONSTRAINT mariopartydsentries_game_id_fkey,
+    ADD CONSTRAINT mariopartydsentries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioPartyIslandTourEntries
+    DROP CONSTRAINT mariopartyislandtourentries_game_id_fkey,
+    ADD CONSTRAINT mariopartyislandtourentries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioPartyStarRushEntries
+    DROP CONSTRAINT mariopartystarrushentries_game_id_fkey,
+    ADD CONSTRAINT mariopartystarrushentries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioPartyTop100Entries
+    DROP CONSTRAINT mariopartytop100entries_game_id_fkey,
+    ADD CONSTRAINT mariopartytop100entries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE SuperMarioPartyEntries
+    DROP CONSTRAINT supermariopartyentries_game_id_fkey,
+    ADD CONSTRAINT supermariopartyentries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioPartySuperstarsEntries
+    DROP CONSTRAINT mariopartysuperstarsentries_game_id_fkey,
+    ADD CONSTRAINT mariopartysuperstarsentries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
+
+ALTER TABLE MarioPartyJamboreeEntries
+    DROP CONSTRAINT mariopartyjamboreeentries_game_id_fkey,
+    ADD CONSTRAINT mariopartyjamboreeentries_game_id_fkey FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE;
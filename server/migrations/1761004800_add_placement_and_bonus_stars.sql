@@ -0,0 +1,142 @@
+CREATE TYPE "BonusStar" AS ENUM ('Minigame Star', 'Coin Star', 'Happening Star', 'Orb Star', 'Running Star', 'Candy Star', 'Shopping Star', 'Item Star', 'Ally Star', 'Slowpoke Star', 'Sightseer Star', 'Eventful Star', 'Unlucky Star');
+
+-- Entries from before placements were recorded get the placement the
+-- leaderboard used to guess for them: most stars then most coins, mini stars
+-- for Mario Party 9 and 10, and spaces moved for Island Tour. Players who tied
+-- on all of those share a placement, since we can't know who won the tiebreak.
+
+ALTER TABLE MarioPartyEntries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioPartyEntries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioPartyEntries) ranked
+    WHERE MarioPartyEntries.id = ranked.id;
+ALTER TABLE MarioPartyEntries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty2Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty2Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioParty2Entries) ranked
+    WHERE MarioParty2Entries.id = ranked.id;
+ALTER TABLE MarioParty2Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty3Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty3Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioParty3Entries) ranked
+    WHERE MarioParty3Entries.id = ranked.id;
+ALTER TABLE MarioParty3Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty4Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty4Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioParty4Entries) ranked
+    WHERE MarioParty4Entries.id = ranked.id;
+ALTER TABLE MarioParty4Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty5Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty5Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioParty5Entries) ranked
+    WHERE MarioParty5Entries.id = ranked.id;
+ALTER TABLE MarioParty5Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty6Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty6Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioParty6Entries) ranked
+    WHERE MarioParty6Entries.id = ranked.id;
+ALTER TABLE MarioParty6Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty7Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty7Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioParty7Entries) ranked
+    WHERE MarioParty7Entries.id = ranked.id;
+ALTER TABLE MarioParty7Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty8Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty8Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioParty8Entries) ranked
+    WHERE MarioParty8Entries.id = ranked.id;
+ALTER TABLE MarioParty8Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty9Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty9Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY mini_stars DESC) AS placement FROM MarioParty9Entries) ranked
+    WHERE MarioParty9Entries.id = ranked.id;
+ALTER TABLE MarioParty9Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioParty10Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioParty10Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY mini_stars DESC) AS placement FROM MarioParty10Entries) ranked
+    WHERE MarioParty10Entries.id = ranked.id;
+ALTER TABLE MarioParty10Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioPartyDSEntries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioPartyDSEntries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioPartyDSEntries) ranked
+    WHERE MarioPartyDSEntries.id = ranked.id;
+ALTER TABLE MarioPartyDSEntries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioPartyIslandTourEntries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioPartyIslandTourEntries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY spaces_moved DESC) AS placement FROM MarioPartyIslandTourEntries) ranked
+    WHERE MarioPartyIslandTourEntries.id = ranked.id;
+ALTER TABLE MarioPartyIslandTourEntries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioPartyStarRushEntries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioPartyStarRushEntries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioPartyStarRushEntries) ranked
+    WHERE MarioPartyStarRushEntries.id = ranked.id;
+ALTER TABLE MarioPartyStarRushEntries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioPartyTop100Entries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioPartyTop100Entries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioPartyTop100Entries) ranked
+    WHERE MarioPartyTop100Entries.id = ranked.id;
+ALTER TABLE MarioPartyTop100Entries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE SuperMarioPartyEntries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE SuperMarioPartyEntries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM SuperMarioPartyEntries) ranked
+    WHERE SuperMarioPartyEntries.id = ranked.id;
+ALTER TABLE SuperMarioPartyEntries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioPartySuperstarsEntries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioPartySuperstarsEntries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioPartySuperstarsEntries) ranked
+    WHERE MarioPartySuperstarsEntries.id = ranked.id;
+ALTER TABLE MarioPartySuperstarsEntries ALTER COLUMN placement SET NOT NULL;
+
+ALTER TABLE MarioPartyJamboreeEntries
+    ADD COLUMN placement INTEGER,
+    ADD COLUMN bonus_stars "BonusStar"[] NOT NULL DEFAULT '{}';
+UPDATE MarioPartyJamboreeEntries SET placement = ranked.placement
+    FROM (SELECT id, RANK() OVER (PARTITION BY game_id ORDER BY stars DESC, coins DESC) AS placement FROM MarioPartyJamboreeEntries) ranked
+    WHERE MarioPartyJamboreeEntries.id = ranked.id;
+ALTER TABLE MarioPartyJamboreeEntries ALTER COLUMN placement SET NOT NULL;
@@ -0,0 +1,210 @@
+-- Games saved without a user can't be seen by anyone, and their entries
+-- would be left without a player below. Their entries go with them.
+DELETE FROM Games WHERE user_id IS NULL;
+
+CREATE TABLE Players (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES Users(id) ON DELETE CASCADE
+);
+
+-- Names are compared ignoring case, so "Jon" and "jon" are the same player.
+-- The server trims names before they get here.
+CREATE UNIQUE INDEX players_user_id_name_idx ON Players (user_id, lower(name));
+
+-- Every name already used in an entry becomes a player. When the same name
+-- was typed with different cases the oldest spelling wins.
+INSERT INTO Players (user_id, name)
+SELECT DISTINCT ON (user_id, lower(name)) user_id, name
+FROM (
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioPartyEntries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty2Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty3Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty4Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty5Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty6Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty7Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty8Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty9Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioParty10Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioPartyDSEntries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioPartyIslandTourEntries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioPartyStarRushEntries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioPartyTop100Entries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM SuperMarioPartyEntries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioPartySuperstarsEntries e JOIN Games ON Games.id = e.game_id
+    UNION ALL
+    SELECT Games.user_id, Games.date, COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player') AS name FROM MarioPartyJamboreeEntries e JOIN Games ON Games.id = e.game_id
+) names
+ORDER BY user_id, lower(name), date;
+
+-- player_name stays on the entries as the name the player went by in that game
+
+ALTER TABLE MarioPartyEntries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioPartyEntries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioPartyEntries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX mariopartyentries_player_id_idx ON MarioPartyEntries (player_id);
+
+ALTER TABLE MarioParty2Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty2Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty2Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty2entries_player_id_idx ON MarioParty2Entries (player_id);
+
+ALTER TABLE MarioParty3Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty3Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty3Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty3entries_player_id_idx ON MarioParty3Entries (player_id);
+
+ALTER TABLE MarioParty4Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty4Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty4Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty4entries_player_id_idx ON MarioParty4Entries (player_id);
+
+ALTER TABLE MarioParty5Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty5Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty5Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty5entries_player_id_idx ON MarioParty5Entries (player_id);
+
+ALTER TABLE MarioParty6Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty6Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty6Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty6entries_player_id_idx ON MarioParty6Entries (player_id);
+
+ALTER TABLE MarioParty7Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty7Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty7Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty7entries_player_id_idx ON MarioParty7Entries (player_id);
+
+ALTER TABLE MarioParty8Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty8Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty8Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty8entries_player_id_idx ON MarioParty8Entries (player_id);
+
+ALTER TABLE MarioParty9Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty9Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty9Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty9entries_player_id_idx ON MarioParty9Entries (player_id);
+
+ALTER TABLE MarioParty10Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioParty10Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioParty10Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX marioparty10entries_player_id_idx ON MarioParty10Entries (player_id);
+
+ALTER TABLE MarioPartyDSEntries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioPartyDSEntries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioPartyDSEntries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX mariopartydsentries_player_id_idx ON MarioPartyDSEntries (player_id);
+
+ALTER TABLE MarioPartyIslandTourEntries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioPartyIslandTourEntries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioPartyIslandTourEntries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX mariopartyislandtourentries_player_id_idx ON MarioPartyIslandTourEntries (player_id);
+
+ALTER TABLE MarioPartyStarRushEntries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioPartyStarRushEntries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioPartyStarRushEntries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX mariopartystarrushentries_player_id_idx ON MarioPartyStarRushEntries (player_id);
+
+ALTER TABLE MarioPartyTop100Entries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioPartyTop100Entries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioPartyTop100Entries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX mariopartytop100entries_player_id_idx ON MarioPartyTop100Entries (player_id);
+
+ALTER TABLE SuperMarioPartyEntries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE SuperMarioPartyEntries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE SuperMarioPartyEntries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX supermariopartyentries_player_id_idx ON SuperMarioPartyEntries (player_id);
+
+ALTER TABLE MarioPartySuperstarsEntries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioPartySuperstarsEntries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioPartySuperstarsEntries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX mariopartysuperstarsentries_player_id_idx ON MarioPartySuperstarsEntries (player_id);
+
+ALTER TABLE MarioPartyJamboreeEntries ADD COLUMN player_id INTEGER REFERENCES Players(id);
+UPDATE MarioPartyJamboreeEntries e SET player_id = Players.id
+    FROM Games, Players
+    WHERE Games.id = e.game_id
+        AND Players.user_id = Games.user_id
+        AND lower(Players.name) = lower(COALESCE(NULLIF(trim(e.player_name), ''), 'Unnamed player'));
+ALTER TABLE MarioPartyJamboreeEntries ALTER COLUMN player_id SET NOT NULL;
+CREATE INDEX mariopartyjamboreeentries_player_id_idx ON MarioPartyJamboreeEntries (player_id);
@@ -0,0 +1,10 @@
+CREATE TABLE Sessions (
+    -- tower_sessions' session id, as it appears in the cookie
+    id TEXT PRIMARY KEY,
+    -- The session's data as JSON
+    data TEXT NOT NULL,
+    expiry_date TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX sessions_expiry_date_idx ON Sessions (expiry_date);
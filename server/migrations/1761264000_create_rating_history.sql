@@ -0,0 +1,17 @@
+-- Every player's rating after each game they were in, from replaying the
+-- user's games in date order. game is the title for per-title ratings, or
+-- NULL for the global rating across every title.
+CREATE TABLE RatingHistory (
+    id SERIAL PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    game TEXT,
+    rating DOUBLE PRECISION NOT NULL,
+    -- How much the game moved the rating
+    change DOUBLE PRECISION NOT NULL,
+    FOREIGN KEY (player_id) REFERENCES Players(id) ON DELETE CASCADE,
+    FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE
+);
+
+CREATE INDEX ratinghistory_player_id_game_idx ON RatingHistory (player_id, game);
+CREATE INDEX ratinghistory_game_id_idx ON RatingHistory (game_id);
@@ -0,0 +1,5 @@
+-- Games that are still being played, entered turn by turn over a websocket.
+-- They don't have any entries until they're finished, the state so far is
+-- kept as JSON in live_state instead.
+ALTER TABLE Games ADD COLUMN draft BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE Games ADD COLUMN live_state TEXT;
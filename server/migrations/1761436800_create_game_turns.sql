@@ -0,0 +1,16 @@
+-- Where each player stood at the end of every turn of a game. stars and
+-- coins are null for games that don't have them, space is the *_spaces
+-- column of the space the player landed on, null if they didn't land on one.
+CREATE TABLE GameTurns (
+    id SERIAL PRIMARY KEY,
+    game_id INTEGER NOT NULL,
+    turn INTEGER NOT NULL CHECK (turn >= 1),
+    player_id INTEGER NOT NULL,
+    stars INTEGER CHECK (stars >= 0),
+    coins INTEGER CHECK (coins >= 0),
+    space TEXT,
+    FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE,
+    FOREIGN KEY (player_id) REFERENCES Players(id)
+);
+
+CREATE UNIQUE INDEX gameturns_game_id_turn_player_id_idx ON GameTurns (game_id, turn, player_id);
@@ -0,0 +1,22 @@
+-- The minigames played in a game. turn is null when nobody wrote down which
+-- turn it was, minigame is a name from the title's catalog in minigames.rs.
+CREATE TABLE MinigameResults (
+    id SERIAL PRIMARY KEY,
+    game_id INTEGER NOT NULL,
+    turn INTEGER CHECK (turn >= 1),
+    minigame TEXT NOT NULL,
+    minigame_type TEXT NOT NULL CHECK (minigame_type IN ('4P', '1v3', '2v2', 'duel', 'battle')),
+    FOREIGN KEY (game_id) REFERENCES Games(id) ON DELETE CASCADE
+);
+
+CREATE INDEX minigameresults_game_id_idx ON MinigameResults (game_id);
+
+-- Who played each minigame, and whether they won it
+CREATE TABLE MinigamePlayers (
+    result_id INTEGER NOT NULL,
+    player_id INTEGER NOT NULL,
+    won BOOLEAN NOT NULL,
+    PRIMARY KEY (result_id, player_id),
+    FOREIGN KEY (result_id) REFERENCES MinigameResults(id) ON DELETE CASCADE,
+    FOREIGN KEY (player_id) REFERENCES Players(id)
+);
@@ -0,0 +1,53 @@
+-- Groups of users who share a history of games and players, like everyone
+-- who comes to the same game night. Every user has a personal group, the one
+-- with personal_for set to them, which is made when they sign up and is
+-- where their games go unless they pick another group.
+CREATE TABLE Groups (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    personal_for INTEGER UNIQUE,
+    FOREIGN KEY (personal_for) REFERENCES Users(id) ON DELETE CASCADE
+);
+
+-- Owners can invite people, change roles and rename or delete the group.
+-- Members can see and add to everything in it
+CREATE TABLE GroupMembers (
+    group_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('owner', 'member')),
+    PRIMARY KEY (group_id, user_id),
+    FOREIGN KEY (group_id) REFERENCES Groups(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES Users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX groupmembers_user_id_idx ON GroupMembers (user_id);
+
+-- Invitations that haven't been accepted or declined yet
+CREATE TABLE GroupInvitations (
+    id SERIAL PRIMARY KEY,
+    group_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    invited_by INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    FOREIGN KEY (group_id) REFERENCES Groups(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES Users(id) ON DELETE CASCADE,
+    FOREIGN KEY (invited_by) REFERENCES Users(id) ON DELETE SET NULL
+);
+
+CREATE UNIQUE INDEX groupinvitations_group_id_user_id_idx ON GroupInvitations (group_id, user_id);
+
+INSERT INTO Groups (name, personal_for) SELECT username, id FROM Users;
+INSERT INTO GroupMembers (group_id, user_id, role) SELECT id, personal_for, 'owner' FROM Groups;
+
+-- Games belong to a group now. user_id stays as whoever added the game
+ALTER TABLE Games ADD COLUMN group_id INTEGER REFERENCES Groups(id) ON DELETE CASCADE;
+UPDATE Games SET group_id = Groups.id FROM Groups WHERE Groups.personal_for = Games.user_id;
+CREATE INDEX games_group_id_idx ON Games (group_id);
+
+-- and so do players, so everyone in a group is talking about the same Jon
+ALTER TABLE Players ADD COLUMN group_id INTEGER REFERENCES Groups(id) ON DELETE CASCADE;
+UPDATE Players SET group_id = Groups.id FROM Groups WHERE Groups.personal_for = Players.user_id;
+ALTER TABLE Players ALTER COLUMN group_id SET NOT NULL;
+DROP INDEX players_user_id_name_idx;
+ALTER TABLE Players DROP COLUMN user_id;
+CREATE UNIQUE INDEX players_group_id_name_idx ON Players (group_id, lower(name));
@@ -0,0 +1,13 @@
+-- What each user is allowed to do. Admins can also list, disable and delete
+-- other users, read-only users can look at their groups but not change them
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user' CHECK (role IN ('admin', 'user', 'read-only'));
+
+-- Disabled users can't log in, and any sessions they already had stop working
+ALTER TABLE users ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT false;
+
+-- Whoever signed up first is the one running the instance
+UPDATE users SET role = 'admin' WHERE id = (SELECT min(id) FROM users);
+
+-- Deleting a user keeps the games they added to groups other people are in
+ALTER TABLE Games DROP CONSTRAINT games_user_id_fkey;
+ALTER TABLE Games ADD CONSTRAINT games_user_id_fkey FOREIGN KEY (user_id) REFERENCES Users(id) ON DELETE SET NULL;
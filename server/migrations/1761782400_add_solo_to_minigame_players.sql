@@ -0,0 +1,13 @@
+-- Which player was on their own in a 1v3 minigame
+ALTER TABLE MinigamePlayers ADD COLUMN solo BOOLEAN NOT NULL DEFAULT false;
+
+-- A 1v3 minigame recorded before this was won by the player on their own,
+-- or by the other three. Ties don't say who it was.
+UPDATE MinigamePlayers p SET solo = (p.won = (w.winners = 1))
+FROM (
+    SELECT r.id, count(*) FILTER (WHERE won) AS winners
+    FROM MinigameResults r JOIN MinigamePlayers ON MinigamePlayers.result_id = r.id
+    WHERE r.minigame_type = '1v3'
+    GROUP BY r.id
+) w
+WHERE p.result_id = w.id AND w.winners IN (1, 3);
@@ -0,0 +1,118 @@
+use sqlx::postgres::PgPool;
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::Path,
+    response::IntoResponse,
+    Json
+};
+use crate::requests::UserUpdate;
+use crate::responses::{AdminUserResponse, MessageResponse};
+use crate::errors::ApiError;
+use crate::routes::{AuthSession, signed_in_user};
+
+// Admins can't lock themselves out, which also means there's always an admin left
+fn not_yourself(admin_id: i32, user_id: i32) -> Result<(), ApiError> {
+    if admin_id == user_id {
+        return Err(ApiError::BadRequest(String::from("Admins can't change or delete their own account from here")));
+    }
+
+    Ok(())
+}
+
+/// Every user, oldest first
+#[axum::debug_handler]
+pub async fn list_users(
+    Extension(pool): Extension<PgPool>
+) -> Result<impl IntoResponse, ApiError> {
+    let users: Vec<AdminUserResponse> = sqlx::query_as("SELECT id, username, role, disabled FROM users ORDER BY id")
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(users))
+}
+
+/// Changes a user's role, or disables or enables them. A disabled user's
+/// sessions stop working straight away.
+#[axum::debug_handler]
+pub async fn update_user(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(user_id): Path<i32>,
+    Json(update): Json<UserUpdate>
+) -> Result<impl IntoResponse, ApiError> {
+    let admin = signed_in_user(auth_session)?;
+    not_yourself(admin.id(), user_id)?;
+
+    let user: Option<AdminUserResponse> = sqlx::query_as("
+            UPDATE users SET role = COALESCE($2, role), disabled = COALESCE($3, disabled)
+            WHERE id = $1
+            RETURNING id, username, role, disabled")
+        .bind(user_id)
+        .bind(update.role.map(|role| role.as_str()))
+        .bind(update.disabled)
+        .fetch_optional(&pool)
+        .await?;
+    let user = user.ok_or_else(|| ApiError::NotFound(String::from("User not found")))?;
+
+    println!("{} updated user {} to {:?}, disabled: {}", admin.username, user.username, user.role, user.disabled);
+
+    Ok(Json(user))
+}
+
+/// Deletes a user along with their personal group. Games they added to
+/// groups other people are in stay there, and anyone left in a group the
+/// user was the only owner of becomes an owner.
+#[axum::debug_handler]
+pub async fn delete_user(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(user_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let admin = signed_in_user(auth_session)?;
+    not_yourself(admin.id(), user_id)?;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("
+            UPDATE GroupMembers SET role = 'owner'
+            WHERE user_id <> $1 AND group_id IN (
+                SELECT group_id FROM GroupMembers
+                WHERE role = 'owner'
+                GROUP BY group_id
+                HAVING bool_and(user_id = $1))")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Nobody could see these any more. Personal groups go with the user
+    // through ON DELETE CASCADE
+    sqlx::query("
+            DELETE FROM Groups
+            WHERE personal_for IS NULL AND id IN (
+                SELECT group_id FROM GroupMembers
+                GROUP BY group_id
+                HAVING bool_and(user_id = $1))")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let result = sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(String::from("User not found")));
+    }
+
+    tx.commit().await?;
+
+    println!("{} deleted user {}", admin.username, user_id);
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Deleted user successfully"),
+            success: true
+        }
+    ))
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, Postgres, Transaction};
+use sqlx::postgres::{PgConnection, PgPool};
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::Query,
+    response::IntoResponse,
+    Json
+};
+use crate::requests::{BackupData, BackupGame, GameData, GroupQuery, GroupRole, MinigameResultData, PlayerRef, RestoreMode, RestoreQuery};
+use crate::responses::{Backup, BackupGameResponse, BackupTurn, PlayerResponse, RejectedGame, RestoreResponse};
+use crate::errors::ApiError;
+use crate::ratings::recompute_from;
+use crate::minigames::{game_minigames, replace_minigames};
+use crate::timeline::replace_turns;
+use crate::routes::{AuthSession, GameRow, check_game, check_stats, game_response, insert_player_data, signed_in_user};
+use crate::groups::{member_group, member_role};
+
+/// The version of the backup format. Bump it whenever a backup made now
+/// couldn't be restored by an older server. Version 2 added each game's
+/// timeline and minigames, older backups are restored without them.
+const BACKUP_VERSION: u32 = 2;
+
+/// Backs up one group, the ?group= or the user's personal group. A user in
+/// several groups backs each of them up on its own, and the backup says which
+/// group it came from.
+#[axum::debug_handler]
+pub async fn backup(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    let group_name: String = sqlx::query_scalar("SELECT name FROM Groups WHERE id = $1")
+        .bind(group_id)
+        .fetch_one(&pool)
+        .await?;
+
+    let players: Vec<PlayerResponse> = sqlx::query_as("SELECT id, name FROM Players WHERE group_id = $1 ORDER BY id")
+        .bind(group_id)
+        .fetch_all(&pool)
+        .await?;
+
+    let rows: Vec<GameRow> = sqlx::query_as("
+            SELECT id, game, board, turns, date FROM Games
+            WHERE group_id = $1 AND game IS NOT NULL AND NOT draft
+            ORDER BY date, id")
+        .bind(group_id)
+        .fetch_all(&pool)
+        .await?;
+
+    let mut conn = pool.acquire().await?;
+    let mut games = Vec::new();
+    for row in rows {
+        let Some(game) = game_response(&pool, row).await? else {
+            continue;
+        };
+
+        let timeline: Vec<BackupTurn> = sqlx::query_as("
+                SELECT turn, player_id, stars, coins, space FROM GameTurns
+                WHERE game_id = $1
+                ORDER BY turn, id")
+            .bind(game.id)
+            .fetch_all(&pool)
+            .await?;
+
+        let minigames = game_minigames(&mut conn, game.id).await?;
+
+        games.push(BackupGameResponse { game, timeline, minigames });
+    }
+
+    Ok(Json(
+        Backup {
+            version: BACKUP_VERSION,
+            created_at: Utc::now(),
+            username: user.username,
+            group_id,
+            group: group_name,
+            players,
+            games
+        }
+    ))
+}
+
+/// Puts a backup into one of the user's groups. Ids are never kept,
+/// players are matched up by name and games get new ids. Each game, with
+/// its turns and minigames, is checked like it would be if it was new, and
+/// the ones that don't pass are left out and listed in the response.
+#[axum::debug_handler]
+pub async fn restore(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<RestoreQuery>,
+    Json(backup): Json<BackupData>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    if backup.version > BACKUP_VERSION {
+        return Err(ApiError::BadRequest(format!(
+            "This backup is version {}, but this server only understands up to version {}",
+            backup.version, BACKUP_VERSION
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    if let RestoreMode::Replace = params.mode {
+        if member_role(&mut tx, group_id, user.id()).await? != Some(GroupRole::Owner) {
+            return Err(ApiError::Forbidden(String::from("Only the group's owners can replace everything in it")));
+        }
+
+        // The *Entries, GameTurns and MinigameResults rows go with the games
+        // through ON DELETE CASCADE, and then nothing is using the players
+        sqlx::query("DELETE FROM Games WHERE group_id = $1")
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM Players WHERE group_id = $1")
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // The backup's player ids to ours. A player with the same name as one
+    // that's already here is the same player
+    let mut player_ids = HashMap::new();
+    for player in &backup.players {
+        let name = player.name.trim();
+        if name.is_empty() {
+            return Err(ApiError::BadRequest(String::from("Player names can't be empty")));
+        }
+
+        let player_id: i32 = sqlx::query_scalar("
+                INSERT INTO Players (group_id, name) VALUES ($1, $2)
+                ON CONFLICT (group_id, lower(name)) DO UPDATE SET name = Players.name
+                RETURNING id")
+            .bind(group_id)
+            .bind(name)
+            .fetch_one(&mut *tx)
+            .await?;
+        player_ids.insert(player.id, player_id);
+    }
+
+    let mut games = 0;
+    let mut skipped_games = 0;
+    let mut rejected_games = Vec::new();
+    let mut earliest = None;
+    for game in backup.games {
+        if let RestoreMode::Merge = params.mode {
+            let exists: bool = sqlx::query_scalar("
+                    SELECT EXISTS (
+                        SELECT 1 FROM Games
+                        WHERE group_id = $1 AND NOT draft AND game = $2 AND board IS NOT DISTINCT FROM $3 AND turns = $4 AND date = $5
+                    )")
+                .bind(group_id)
+                .bind(game.player_data.game_name())
+                .bind(&game.board)
+                .bind(game.turns)
+                .bind(game.date)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            if exists {
+                skipped_games += 1;
+                continue;
+            }
+        }
+
+        let (id, date) = (game.id, game.date);
+        let name = game.player_data.game_name();
+        match restore_game(&mut tx, user.id(), group_id, &player_ids, game).await? {
+            Ok(()) => {
+                earliest = Some(earliest.map_or(date, |earliest: DateTime<Utc>| earliest.min(date)));
+                games += 1;
+            },
+            Err(reason) => {
+                rejected_games.push(RejectedGame { id, game: name.to_string(), reason });
+            }
+        }
+    }
+
+    if let Some(earliest) = earliest {
+        recompute_from(&mut tx, group_id, earliest).await?;
+    }
+
+    tx.commit().await?;
+
+    println!("Restored {} players and {} games, skipped {} and rejected {}", player_ids.len(), games, skipped_games, rejected_games.len());
+
+    Ok(Json(
+        RestoreResponse {
+            players: player_ids.len(),
+            games,
+            skipped_games,
+            rejected_games
+        }
+    ))
+}
+
+// Ok(Err(reason)) if the game was rejected. Err is only for things going
+// wrong on our end, which stop the whole restore.
+async fn restore_game(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    group_id: i32,
+    player_ids: &HashMap<i32, i32>,
+    game: BackupGame
+) -> Result<Result<(), String>, ApiError> {
+    // A savepoint, so a game that fails doesn't take the others with it
+    let mut savepoint = tx.begin().await?;
+    match insert_backup_game(&mut savepoint, user_id, group_id, player_ids, game).await {
+        Ok(()) => {
+            savepoint.commit().await?;
+            Ok(Ok(()))
+        },
+        Err(err) => {
+            savepoint.rollback().await?;
+            match err.status_and_message() {
+                (status, message) if status.is_client_error() => Ok(Err(message)),
+                _ => Err(err)
+            }
+        }
+    }
+}
+
+// Swaps the backup's player ids for ours and adds the game, checking it and
+// then its turns and minigames the same way the endpoints for them do
+async fn insert_backup_game(
+    conn: &mut PgConnection,
+    user_id: i32,
+    group_id: i32,
+    player_ids: &HashMap<i32, i32>,
+    game: BackupGame
+) -> Result<(), ApiError> {
+    let BackupGame { id: _, mut player_data, board, turns, date, mut timeline, minigames } = game;
+
+    let new_id = |id: Option<i32>| {
+        id.and_then(|id| player_ids.get(&id).copied())
+            .ok_or_else(|| ApiError::BadRequest(String::from("It has a player that isn't in the backup's players")))
+    };
+    for (player_id, _) in player_data.players_mut() {
+        *player_id = Some(new_id(*player_id)?);
+    }
+    for snapshot in &mut timeline {
+        snapshot.player_id = Some(new_id(snapshot.player_id)?);
+    }
+    let minigames: Vec<MinigameResultData> = minigames.into_iter().map(|minigame| {
+        let players = minigame.players.iter().map(|player| new_id(Some(player.id)).map(PlayerRef::Id)).collect::<Result<_, _>>()?;
+        let winners = minigame.winners.iter().map(|player| new_id(Some(player.id)).map(PlayerRef::Id)).collect::<Result<_, _>>()?;
+        let solo = minigame.solo.map(|player| new_id(Some(player.id)).map(PlayerRef::Id)).transpose()?;
+        Ok(MinigameResultData {
+            turn: minigame.turn,
+            minigame: minigame.minigame,
+            minigame_type: minigame.minigame_type,
+            players: Some(players),
+            winners,
+            solo
+        })
+    }).collect::<Result<_, ApiError>>()?;
+
+    // Games from before boards were kept don't have one
+    let data = GameData { player_data, board: board.clone().unwrap_or_default(), turns, date };
+    match board {
+        Some(_) => check_game(&data)?,
+        None => check_stats(&data)?
+    }
+
+    let game_id: i32 = sqlx::query_scalar("INSERT INTO Games (user_id, group_id, date, turns, board, game) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id")
+        .bind(user_id)
+        .bind(group_id)
+        .bind(date)
+        .bind(turns)
+        .bind(board)
+        .bind(data.player_data.game_name())
+        .fetch_one(&mut *conn)
+        .await?;
+    insert_player_data(&mut *conn, game_id, data.player_data).await?;
+
+    replace_turns(&mut *conn, game_id, user_id, timeline).await?;
+    replace_minigames(&mut *conn, game_id, user_id, &minigames).await?;
+
+    Ok(())
+}
@@ -0,0 +1,367 @@
+use serde::{Serialize, Deserialize, de::IntoDeserializer};
+use listfields_derive::ListFields;
+use crate::listfields::{ObjectData, EnumData, Variant, ListFields};
+use crate::requests::MarioPartyData;
+
+// The serde names here are what the client sends as the `board` field of
+// `GameData` and what gets stored in the Games.board column, so don't
+// change them without a migration.
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioPartyBoards {
+    #[serde(rename = "DK's Jungle Adventure")]
+    DKsJungleAdventure,
+    #[serde(rename = "Peach's Birthday Cake")]
+    PeachsBirthdayCake,
+    #[serde(rename = "Yoshi's Tropical Island")]
+    YoshisTropicalIsland,
+    #[serde(rename = "Wario's Battle Canyon")]
+    WariosBattleCanyon,
+    #[serde(rename = "Luigi's Engine Room")]
+    LuigisEngineRoom,
+    #[serde(rename = "Mario's Rainbow Castle")]
+    MariosRainbowCastle,
+    #[serde(rename = "Bowser's Magma Mountain")]
+    BowsersMagmaMountain,
+    #[serde(rename = "Eternal Star")]
+    EternalStar,
+    #[serde(rename = "Mini-Game Stadium")]
+    MiniGameStadium
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty2Boards {
+    #[serde(rename = "Pirate Land")]
+    PirateLand,
+    #[serde(rename = "Western Land")]
+    WesternLand,
+    #[serde(rename = "Space Land")]
+    SpaceLand,
+    #[serde(rename = "Mystery Land")]
+    MysteryLand,
+    #[serde(rename = "Horror Land")]
+    HorrorLand,
+    #[serde(rename = "Bowser Land")]
+    BowserLand,
+    #[serde(rename = "Mini-Game Trial")]
+    MiniGameTrial
+}
+
+// Not including duel boards for now
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty3Boards {
+    #[serde(rename = "Chilly Waters")]
+    ChillyWaters,
+    #[serde(rename = "Deep Bloober Sea")]
+    DeepBlooberSea,
+    #[serde(rename = "Spiny Desert")]
+    SpinyDesert,
+    #[serde(rename = "Woody Woods")]
+    WoodyWoods,
+    #[serde(rename = "Creepy Cavern")]
+    CreepyCavern,
+    #[serde(rename = "Waluigi's Island")]
+    WaluigisIsland
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty4Boards {
+    #[serde(rename = "Toad's Midway Madness")]
+    ToadsMidwayMadness,
+    #[serde(rename = "Goomba's Greedy Gala")]
+    GoombasGreedyGala,
+    #[serde(rename = "Boo's Haunted Bash")]
+    BoosHauntedBash,
+    #[serde(rename = "Koopa's Seaside Soiree")]
+    KoopasSeasideSoiree,
+    #[serde(rename = "Shy Guy's Jungle Jam")]
+    ShyGuysJungleJam,
+    #[serde(rename = "Bowser's Gnarly Party")]
+    BowsersGnarlyParty,
+    #[serde(rename = "Mega Board Mayhem")]
+    MegaBoardMayhem,
+    #[serde(rename = "Mini Board Mad-Dash")]
+    MiniBoardMadDash
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty5Boards {
+    #[serde(rename = "Toy Dream")]
+    ToyDream,
+    #[serde(rename = "Rainbow Dream")]
+    RainbowDream,
+    #[serde(rename = "Pirate Dream")]
+    PirateDream,
+    #[serde(rename = "Undersea Dream")]
+    UnderseaDream,
+    #[serde(rename = "Future Dream")]
+    FutureDream,
+    #[serde(rename = "Sweet Dream")]
+    SweetDream,
+    #[serde(rename = "Bowser Nightmare")]
+    BowserNightmare
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty6Boards {
+    #[serde(rename = "Towering Treetop")]
+    ToweringTreetop,
+    #[serde(rename = "E. Gadd's Garage")]
+    EGaddsGarage,
+    #[serde(rename = "Faire Square")]
+    FaireSquare,
+    #[serde(rename = "Snowflake Lake")]
+    SnowflakeLake,
+    #[serde(rename = "Castaway Bay")]
+    CastawayBay,
+    #[serde(rename = "Clockwork Castle")]
+    ClockworkCastle,
+    #[serde(rename = "Thirsty Gulch")]
+    ThirstyGulch,
+    #[serde(rename = "Astro Avenue")]
+    AstroAvenue,
+    #[serde(rename = "Infernal Tower")]
+    InfernalTower
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty7Boards {
+    #[serde(rename = "Grand Canal")]
+    GrandCanal,
+    #[serde(rename = "Pagoda Peak")]
+    PagodaPeak,
+    #[serde(rename = "Pyramid Park")]
+    PyramidPark,
+    #[serde(rename = "Neon Heights")]
+    NeonHeights,
+    Windmillville,
+    #[serde(rename = "Bowser's Enchanted Inferno!")]
+    BowsersEnchantedInferno
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty8Boards {
+    #[serde(rename = "DK's Treetop Temple")]
+    DKsTreetopTemple,
+    #[serde(rename = "Goomba's Booty Boardwalk")]
+    GoombasBootyBoardwalk,
+    #[serde(rename = "King Boo's Haunted Hideaway")]
+    KingBoosHauntedHideaway,
+    #[serde(rename = "Shy Guy's Perplex Express")]
+    ShyGuysPerplexExpress,
+    #[serde(rename = "Koopa's Tycoon Town")]
+    KoopasTycoonTown,
+    #[serde(rename = "Bowser's Warped Orbit")]
+    BowsersWarpedOrbit
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty9Boards {
+    #[serde(rename = "Toad Road")]
+    ToadRoad,
+    #[serde(rename = "Bob-omb Factory")]
+    BobombFactory,
+    #[serde(rename = "Boo's Horror Castle")]
+    BoosHorrorCastle,
+    #[serde(rename = "Blooper Beach")]
+    BlooperBeach,
+    #[serde(rename = "Magma Mine")]
+    MagmaMine,
+    #[serde(rename = "Bowser Station")]
+    BowserStation,
+    #[serde(rename = "DK's Jungle Ruins")]
+    DKsJungleRuins
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioParty10Boards {
+    #[serde(rename = "Mushroom Park")]
+    MushroomPark,
+    #[serde(rename = "Haunted Trail")]
+    HauntedTrail,
+    #[serde(rename = "Whimsical Waters")]
+    WhimsicalWaters,
+    #[serde(rename = "Airship Central")]
+    AirshipCentral,
+    #[serde(rename = "Chaos Castle")]
+    ChaosCastle
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioPartyDSBoards {
+    #[serde(rename = "Wiggler's Garden")]
+    WigglersGarden,
+    #[serde(rename = "Toadette's Music Room")]
+    ToadettesMusicRoom,
+    #[serde(rename = "DK's Stone Statue")]
+    DKsStoneStatue,
+    #[serde(rename = "Kamek's Library")]
+    KameksLibrary,
+    #[serde(rename = "Bowser's Pinball Machine")]
+    BowsersPinballMachine
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioPartyIslandTourBoards {
+    #[serde(rename = "Perilous Palace Path")]
+    PerilousPalacePath,
+    #[serde(rename = "Rocket Road")]
+    RocketRoad,
+    #[serde(rename = "Shy Guy's Shuffle City")]
+    ShyGuysShuffleCity,
+    #[serde(rename = "Banzai Bill's Mad Mountain")]
+    BanzaiBillsMadMountain,
+    #[serde(rename = "Star-Crossed Skyway")]
+    StarCrossedSkyway,
+    #[serde(rename = "Kamek's Carpet Ride")]
+    KameksCarpetRide,
+    #[serde(rename = "Bowser's Peculiar Peak")]
+    BowsersPeculiarPeak
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioPartyStarRushBoards {
+    #[serde(rename = "World 0-1")]
+    World01,
+    #[serde(rename = "World 0-2")]
+    World02,
+    #[serde(rename = "World 0-3")]
+    World03,
+    #[serde(rename = "World 1-1")]
+    World11,
+    #[serde(rename = "World 1-2")]
+    World12,
+    #[serde(rename = "World 1-3")]
+    World13,
+    #[serde(rename = "World 2-1")]
+    World21,
+    #[serde(rename = "World 2-2")]
+    World22,
+    #[serde(rename = "World 2-3")]
+    World23,
+    #[serde(rename = "World 3-1")]
+    World31,
+    #[serde(rename = "World 3-2")]
+    World32,
+    #[serde(rename = "World 3-3")]
+    World33,
+    #[serde(rename = "World 4-1")]
+    World41,
+    #[serde(rename = "World 4-2")]
+    World42,
+    #[serde(rename = "World 4-3")]
+    World43,
+    Coinathlon,
+    #[serde(rename = "Map 1 (Balloon Bash)")]
+    BalloonBashMap1,
+    #[serde(rename = "Map 2 (Balloon Bash)")]
+    BalloonBashMap2,
+    #[serde(rename = "Map 3 (Balloon Bash)")]
+    BalloonBashMap3
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioPartyTop100Boards {
+    #[serde(rename = "Minigame Match")]
+    MinigameMatch
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum SuperMarioPartyBoards {
+    #[serde(rename = "Whomp's Domino Ruins")]
+    WhompsDominoRuins,
+    #[serde(rename = "King Bob-omb's Powderkeg Mine")]
+    KingBobombsPowderkegMine,
+    #[serde(rename = "Megafruit Paradise")]
+    MegafruitParadise,
+    #[serde(rename = "Kamek's Tantalizing Tower")]
+    KameksTantalizingTower
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioPartySuperstarsBoards {
+    #[serde(rename = "Peach's Birthday Cake")]
+    PeachsBirthdayCake,
+    #[serde(rename = "Yoshi's Tropical Island")]
+    YoshisTropicalIsland,
+    #[serde(rename = "Space Land")]
+    SpaceLand,
+    #[serde(rename = "Horror Land")]
+    HorrorLand,
+    #[serde(rename = "Woody Woods")]
+    WoodyWoods
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
+pub enum MarioPartyJamboreeBoards {
+    #[serde(rename = "Mega Wiggler's Tree Party")]
+    MegaWigglersTreeParty,
+    #[serde(rename = "Roll 'em Raceway")]
+    RollEmRaceway,
+    #[serde(rename = "Rainbow Galleria")]
+    RainbowGalleria,
+    #[serde(rename = "Goomba Lagoon")]
+    GoombaLagoon,
+    #[serde(rename = "King Bowser's Keep")]
+    KingBowsersKeep,
+    #[serde(rename = "Western Land")]
+    WesternLand,
+    #[serde(rename = "Mario's Rainbow Castle")]
+    MariosRainbowCastle
+}
+
+// Deserializing the board name into the game's board enum is the same check
+// serde does for the character enums when the request body is parsed
+fn is_board<'de, T: Deserialize<'de>>(board: &'de str) -> bool {
+    let deserializer: serde::de::value::StrDeserializer<serde::de::value::Error> = board.into_deserializer();
+    T::deserialize(deserializer).is_ok()
+}
+
+impl MarioPartyData {
+    /// Returns whether `board` is one of the boards of the game this data is for
+    pub fn is_valid_board(&self, board: &str) -> bool {
+        match self {
+            MarioPartyData::MarioParty(_) => is_board::<MarioPartyBoards>(board),
+            MarioPartyData::MarioParty2(_) => is_board::<MarioParty2Boards>(board),
+            MarioPartyData::MarioParty3(_) => is_board::<MarioParty3Boards>(board),
+            MarioPartyData::MarioParty4(_) => is_board::<MarioParty4Boards>(board),
+            MarioPartyData::MarioParty5(_) => is_board::<MarioParty5Boards>(board),
+            MarioPartyData::MarioParty6(_) => is_board::<MarioParty6Boards>(board),
+            MarioPartyData::MarioParty7(_) => is_board::<MarioParty7Boards>(board),
+            MarioPartyData::MarioParty8(_) => is_board::<MarioParty8Boards>(board),
+            MarioPartyData::MarioParty9(_) => is_board::<MarioParty9Boards>(board),
+            MarioPartyData::MarioParty10(_) => is_board::<MarioParty10Boards>(board),
+            MarioPartyData::MarioPartyDS(_) => is_board::<MarioPartyDSBoards>(board),
+            MarioPartyData::MarioPartyIslandTour(_) => is_board::<MarioPartyIslandTourBoards>(board),
+            MarioPartyData::MarioPartyStarRush(_) => is_board::<MarioPartyStarRushBoards>(board),
+            MarioPartyData::MarioPartyTop100(_) => is_board::<MarioPartyTop100Boards>(board),
+            MarioPartyData::SuperMarioParty(_) => is_board::<SuperMarioPartyBoards>(board),
+            MarioPartyData::MarioPartySuperstars(_) => is_board::<MarioPartySuperstarsBoards>(board),
+            MarioPartyData::MarioPartyJamboree(_) => is_board::<MarioPartyJamboreeBoards>(board)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_the_games_own_boards() {
+        assert!(MarioPartyData::MarioParty(vec![]).is_valid_board("Eternal Star"));
+        assert!(MarioPartyData::MarioParty2(vec![]).is_valid_board("Western Land"));
+        assert!(MarioPartyData::MarioParty2(vec![]).is_valid_board("Mini-Game Trial"));
+    }
+
+    #[test]
+    fn rejects_boards_from_other_games() {
+        assert!(!MarioPartyData::MarioParty(vec![]).is_valid_board("Pirate Land"));
+        assert!(!MarioPartyData::MarioParty2(vec![]).is_valid_board("DK's Jungle Adventure"));
+    }
+
+    #[test]
+    fn rejects_variant_names_and_other_spellings() {
+        assert!(!MarioPartyData::MarioParty2(vec![]).is_valid_board("WesternLand"));
+        assert!(!MarioPartyData::MarioParty2(vec![]).is_valid_board("western land"));
+        assert!(!MarioPartyData::MarioParty2(vec![]).is_valid_board(""));
+    }
+}
@@ -0,0 +1,79 @@
+use sqlx::postgres::{PgArguments, PgConnection, PgPool, PgRow, Postgres};
+use sqlx::query::Query;
+use sqlx::FromRow;
+
+/// How one player's stats for a game are stored. Derived with
+/// `#[derive(EntryTable)]`, which keeps the columns in step with the struct's
+/// fields, so adding a stat only needs the field and a migration.
+pub trait EntryTable {
+    /// The *Entries table
+    const TABLE: &'static str;
+    /// Every column the struct has a field for, in the order bind_all binds them
+    const COLUMNS: &'static [&'static str];
+
+    /// Binds every field to `query`, in the order of COLUMNS
+    fn bind_all<'q>(self, query: Query<'q, Postgres, PgArguments>) -> Query<'q, Postgres, PgArguments>;
+}
+
+/// Inserts `entries` for the game `game_id`
+pub async fn insert_entries<T: EntryTable>(conn: &mut PgConnection, game_id: i32, entries: Vec<T>) -> Result<(), sqlx::Error> {
+    let placeholders: Vec<String> = (2..T::COLUMNS.len() + 2).map(|i| format!("${i}")).collect();
+    let sql = format!(
+        "INSERT INTO {} (game_id, {}) VALUES ($1, {})",
+        T::TABLE,
+        T::COLUMNS.join(", "),
+        placeholders.join(", ")
+    );
+
+    for entry in entries {
+        entry.bind_all(sqlx::query(&sql).bind(game_id))
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Every entry for the game `game_id`, in the order they were inserted
+pub async fn fetch_entries<T>(pool: &PgPool, game_id: i32) -> Result<Vec<T>, sqlx::Error>
+where
+    T: EntryTable + for<'r> FromRow<'r, PgRow> + Send + Unpin
+{
+    let sql = format!("SELECT {} FROM {} WHERE game_id = $1 ORDER BY id", T::COLUMNS.join(", "), T::TABLE);
+
+    sqlx::query_as(&sql)
+        .bind(game_id)
+        .fetch_all(pool)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::listfields::{ListFields, ObjectData};
+    use crate::requests::{MarioParty2, MarioParty9, MarioPartyJamboree};
+
+    // The columns have to be the struct's fields, in the same order
+    fn field_names<T: ListFields>() -> Vec<String> {
+        match T::list_fields() {
+            ObjectData::Fields(fields) => fields.into_iter().map(|field| field.name).collect(),
+            ObjectData::EnumData(_) => panic!("entry structs aren't enums")
+        }
+    }
+
+    #[test]
+    fn table_names() {
+        assert_eq!(MarioParty2::TABLE, "MarioParty2Entries");
+        assert_eq!(MarioPartyJamboree::TABLE, "MarioPartyJamboreeEntries");
+    }
+
+    #[test]
+    fn columns_follow_the_fields() {
+        assert_eq!(&MarioParty2::COLUMNS[..4], ["player_id", "player_name", "character", "stars"]);
+        assert_eq!(MarioParty2::COLUMNS.last(), Some(&"bonus_stars"));
+
+        assert_eq!(MarioParty2::COLUMNS, field_names::<MarioParty2>());
+        assert_eq!(MarioParty9::COLUMNS, field_names::<MarioParty9>());
+        assert_eq!(MarioPartyJamboree::COLUMNS, field_names::<MarioPartyJamboree>());
+    }
+}
@@ -0,0 +1,175 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json
+};
+use crate::responses::MessageResponse;
+use crate::routes::Backend;
+
+// Postgres error codes we treat as the client's fault.
+// https://www.postgresql.org/docs/current/errcodes-appendix.html
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+const NOT_NULL_VIOLATION: &str = "23502";
+const CHECK_VIOLATION: &str = "23514";
+// This is what you get when a string can't be cast to one of our sql enums
+const INVALID_TEXT_REPRESENTATION: &str = "22P02";
+
+/// The error type for every handler. However it's created, it always gets
+/// sent to the client as a `MessageResponse` so failures look the same
+/// everywhere.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    Unauthorized(String),
+
+    #[error("{0}")]
+    Forbidden(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Auth(#[from] axum_login::Error<Backend>),
+
+    #[error("{0}")]
+    Internal(String)
+}
+
+impl ApiError {
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
+            ApiError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, message.clone()),
+            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message.clone()),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message.clone()),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message.clone()),
+            ApiError::Conflict(message) => (StatusCode::CONFLICT, message.clone()),
+            ApiError::Sqlx(sqlx::Error::RowNotFound) => {
+                (StatusCode::NOT_FOUND, String::from("Not found"))
+            },
+            ApiError::Sqlx(sqlx::Error::Database(e)) => {
+                match e.code().as_deref() {
+                    Some(UNIQUE_VIOLATION) => (StatusCode::CONFLICT, e.message().to_string()),
+                    Some(FOREIGN_KEY_VIOLATION) => (StatusCode::CONFLICT, e.message().to_string()),
+                    Some(NOT_NULL_VIOLATION)
+                        | Some(CHECK_VIOLATION)
+                        | Some(INVALID_TEXT_REPRESENTATION) => (StatusCode::BAD_REQUEST, e.message().to_string()),
+                    _ => (StatusCode::INTERNAL_SERVER_ERROR, String::from("Server error"))
+                }
+            },
+            ApiError::Sqlx(_) | ApiError::Auth(_) | ApiError::Internal(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, String::from("Server error"))
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = self.status_and_message();
+        if status.is_server_error() {
+            println!("{}", self);
+        }
+
+        (
+            status,
+            Json(
+                MessageResponse {
+                    message,
+                    success: false
+                }
+            )
+        ).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use std::error::Error as StdError;
+    use sqlx::error::{DatabaseError, ErrorKind};
+
+    // Stands in for a PgDatabaseError, which can't be made outside of sqlx
+    #[derive(Debug)]
+    struct FakeDatabaseError(&'static str);
+
+    impl std::fmt::Display for FakeDatabaseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "error {}", self.0)
+        }
+    }
+
+    impl StdError for FakeDatabaseError {}
+
+    impl DatabaseError for FakeDatabaseError {
+        fn message(&self) -> &str {
+            "violates a constraint"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    fn database_error(code: &'static str) -> ApiError {
+        ApiError::Sqlx(sqlx::Error::Database(Box::new(FakeDatabaseError(code))))
+    }
+
+    #[test]
+    fn client_errors_keep_their_message() {
+        assert_eq!(ApiError::Unauthorized(String::from("a")).status_and_message(), (StatusCode::UNAUTHORIZED, String::from("a")));
+        assert_eq!(ApiError::Forbidden(String::from("b")).status_and_message(), (StatusCode::FORBIDDEN, String::from("b")));
+        assert_eq!(ApiError::NotFound(String::from("c")).status_and_message(), (StatusCode::NOT_FOUND, String::from("c")));
+        assert_eq!(ApiError::BadRequest(String::from("d")).status_and_message(), (StatusCode::BAD_REQUEST, String::from("d")));
+        assert_eq!(ApiError::Conflict(String::from("e")).status_and_message(), (StatusCode::CONFLICT, String::from("e")));
+    }
+
+    #[test]
+    fn missing_rows_are_not_found() {
+        assert_eq!(ApiError::Sqlx(sqlx::Error::RowNotFound).status_and_message(), (StatusCode::NOT_FOUND, String::from("Not found")));
+    }
+
+    #[test]
+    fn constraint_violations_are_the_clients_fault() {
+        let message = String::from("violates a constraint");
+        assert_eq!(database_error(UNIQUE_VIOLATION).status_and_message(), (StatusCode::CONFLICT, message.clone()));
+        assert_eq!(database_error(FOREIGN_KEY_VIOLATION).status_and_message(), (StatusCode::CONFLICT, message.clone()));
+        assert_eq!(database_error(NOT_NULL_VIOLATION).status_and_message(), (StatusCode::BAD_REQUEST, message.clone()));
+        assert_eq!(database_error(CHECK_VIOLATION).status_and_message(), (StatusCode::BAD_REQUEST, message.clone()));
+        assert_eq!(database_error(INVALID_TEXT_REPRESENTATION).status_and_message(), (StatusCode::BAD_REQUEST, message));
+    }
+
+    #[test]
+    fn server_errors_hide_the_details() {
+        let server_error = (StatusCode::INTERNAL_SERVER_ERROR, String::from("Server error"));
+        assert_eq!(database_error("42P01").status_and_message(), server_error);
+        assert_eq!(ApiError::Sqlx(sqlx::Error::PoolTimedOut).status_and_message(), server_error);
+        assert_eq!(ApiError::Internal(String::from("disk on fire")).status_and_message(), server_error);
+    }
+}
@@ -0,0 +1,216 @@
+use std::io::{Cursor, Write};
+use futures::{Stream, StreamExt, TryStreamExt};
+use sqlx::Row;
+use sqlx::postgres::{PgPool, PgRow};
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    body::Body,
+    extract::Query,
+    http::header,
+    response::IntoResponse
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+use crate::listfields::Field;
+use crate::requests::{ExportQuery, GroupQuery, MarioPartyData, titles};
+use crate::errors::ApiError;
+use crate::routes::{AuthSession, signed_in_user};
+use crate::groups::member_group;
+
+/// The columns from Games that start every row, before the player's stats
+pub const GAME_COLUMNS: [&str; 5] = ["game_id", "game", "board", "turns", "date"];
+
+/// The query for every row of one title's export, with each column cast to
+/// text the way it's written in the CSV. Lists, like bonus_stars, are joined
+/// with semicolons.
+fn export_query(table: &str, fields: &[Field]) -> String {
+    let columns: Vec<String> = fields.iter().map(|field| {
+        if field.list {
+            format!("array_to_string(e.{0}, ';') AS {0}", field.name)
+        } else {
+            format!("e.{0}::text AS {0}", field.name)
+        }
+    }).collect();
+
+    format!("
+        SELECT
+            Games.id::text AS game_id,
+            Games.game,
+            Games.board,
+            Games.turns::text AS turns,
+            to_char(Games.date AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"') AS date,
+            {}
+        FROM {table} e
+            JOIN Games ON Games.id = e.game_id
+        WHERE Games.group_id = $1
+        ORDER BY Games.date, Games.id, e.id",
+        columns.join(",\n            ")
+    )
+}
+
+fn csv_line<I, T>(record: I) -> Result<Vec<u8>, ApiError>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(record)
+        .map_err(|err| ApiError::Internal(err.to_string()))?;
+
+    writer.into_inner()
+        .map_err(|err| ApiError::Internal(err.to_string()))
+}
+
+/// The header of a title's CSV, which is also the layout /api/import reads
+pub fn header_line(fields: &[Field]) -> Result<Vec<u8>, ApiError> {
+    csv_line(GAME_COLUMNS.iter().copied().chain(fields.iter().map(|field| field.name.as_str())))
+}
+
+fn row_line(row: &PgRow) -> Result<Vec<u8>, ApiError> {
+    let mut record = Vec::new();
+    for i in 0..row.len() {
+        let value: Option<String> = row.try_get(i)?;
+        record.push(value.unwrap_or_default());
+    }
+
+    csv_line(record)
+}
+
+// "Mario Party: The Top 100" -> "mario-party-the-top-100.csv"
+fn file_name(game: &str) -> String {
+    let words: Vec<String> = game
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+
+    format!("{}.csv", words.join("-"))
+}
+
+// The CSV lines for every row of `sql`, written as they come out of the
+// database so a big history never has to fit in memory
+fn row_lines(pool: PgPool, sql: String, group_id: i32) -> impl Stream<Item = Result<Vec<u8>, ApiError>> {
+    async_stream::try_stream! {
+        let mut rows = sqlx::query(&sql)
+            .bind(group_id)
+            .fetch(&pool);
+        while let Some(row) = rows.try_next().await? {
+            yield row_line(&row)?;
+        }
+    }
+}
+
+#[axum::debug_handler]
+pub async fn export_csv(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<ExportQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    let game = params.game.ok_or_else(|| {
+        ApiError::BadRequest(String::from("Pick a game with ?game=, or get every game from /api/export.zip"))
+    })?;
+    let fields = titles().into_iter().find(|(name, _)| *name == game).map(|(_, fields)| fields);
+    let (Some(table), Some(fields)) = (MarioPartyData::entries_table(&game), fields) else {
+        return Err(ApiError::BadRequest(format!("\"{}\" is not a game", game)));
+    };
+
+    let header = header_line(&fields)?;
+    let rows = row_lines(pool, export_query(table, &fields), group_id);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, String::from("text/csv; charset=utf-8")),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name(&game)))
+        ],
+        Body::from_stream(futures::stream::once(async { Ok(header) }).chain(rows))
+    ))
+}
+
+#[axum::debug_handler]
+pub async fn export_zip(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    // One CSV per title the user has played, laid out like export_csv's
+    let mut files = Vec::new();
+    for (game, fields) in titles() {
+        let Some(table) = MarioPartyData::entries_table(&game) else {
+            continue;
+        };
+
+        let rows = sqlx::query(&export_query(table, &fields))
+            .bind(group_id)
+            .fetch_all(&pool)
+            .await?;
+        if rows.is_empty() {
+            continue;
+        }
+
+        let mut csv = header_line(&fields)?;
+        for row in &rows {
+            csv.extend(row_line(row)?);
+        }
+        files.push((file_name(&game), csv));
+    }
+
+    // Compressing is blocking work
+    let zip = tokio::task::spawn_blocking(move || -> zip::result::ZipResult<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, csv) in files {
+            zip.start_file(name, options)?;
+            zip.write_all(&csv)?;
+        }
+
+        Ok(zip.finish()?.into_inner())
+    })
+    .await
+    .map_err(|err| ApiError::Internal(err.to_string()))?
+    .map_err(|err| ApiError::Internal(err.to_string()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"mario-party-tracker.zip\"")
+        ],
+        zip
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names() {
+        assert_eq!(file_name("Mario Party"), "mario-party.csv");
+        assert_eq!(file_name("Mario Party: The Top 100"), "mario-party-the-top-100.csv");
+        assert_eq!(file_name("Mario Party DS"), "mario-party-ds.csv");
+    }
+
+    #[test]
+    fn file_names_are_unique() {
+        let mut names: Vec<String> = titles().iter().map(|(game, _)| file_name(game)).collect();
+        let count = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+
+    #[test]
+    fn csv_lines_quote_when_needed() {
+        let line = csv_line(["1", "Mario Party: The Top 100", "Minigame Star;Coin Star", "a \"b\", c"]).unwrap();
+        assert_eq!(
+            String::from_utf8(line).unwrap(),
+            "1,Mario Party: The Top 100,Minigame Star;Coin Star,\"a \"\"b\"\", c\"\n"
+        );
+    }
+}
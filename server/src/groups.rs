@@ -0,0 +1,576 @@
+use sqlx::postgres::{PgConnection, PgPool};
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::Path,
+    response::IntoResponse,
+    Json
+};
+use crate::requests::{GroupData, GroupRole, InvitationData, MemberRole};
+use crate::responses::{GroupDetails, GroupMember, GroupResponse, InvitationResponse, MessageResponse};
+use crate::errors::ApiError;
+use crate::routes::{AuthSession, signed_in_user};
+
+/// A user's personal group, made if they don't have one yet. Everyone gets
+/// one when they sign up, this only makes it for users who somehow don't.
+pub async fn personal_group(conn: &mut PgConnection, user_id: i32) -> Result<i32, sqlx::Error> {
+    let group_id: i32 = sqlx::query_scalar("
+            INSERT INTO Groups (name, personal_for)
+            SELECT username, id FROM Users WHERE id = $1
+            ON CONFLICT (personal_for) DO UPDATE SET name = Groups.name
+            RETURNING id")
+        .bind(user_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    sqlx::query("INSERT INTO GroupMembers (group_id, user_id, role) VALUES ($1, $2, 'owner') ON CONFLICT DO NOTHING")
+        .bind(group_id)
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(group_id)
+}
+
+/// The user's role in a group, or None if they aren't in it
+pub async fn member_role(conn: &mut PgConnection, group_id: i32, user_id: i32) -> Result<Option<GroupRole>, sqlx::Error> {
+    let role: Option<String> = sqlx::query_scalar("SELECT role FROM GroupMembers WHERE group_id = $1 AND user_id = $2")
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(role.as_deref().and_then(GroupRole::from_name))
+}
+
+/// The group a request is about: the ?group= it was given, as long as the
+/// user is in it, or their personal group if it wasn't given one
+pub async fn member_group(pool: &PgPool, user_id: i32, group: Option<i32>) -> Result<i32, ApiError> {
+    let mut conn = pool.acquire().await?;
+
+    match group {
+        Some(group_id) => {
+            member_role(&mut conn, group_id, user_id).await?
+                .map(|_| group_id)
+                .ok_or_else(|| ApiError::NotFound(String::from("Group not found")))
+        },
+        None => Ok(personal_group(&mut conn, user_id).await?)
+    }
+}
+
+// Owners can change the group, Not Found if they aren't even in it
+fn check_owner(role: Option<GroupRole>) -> Result<(), ApiError> {
+    match role {
+        Some(GroupRole::Owner) => Ok(()),
+        Some(GroupRole::Member) => Err(ApiError::Forbidden(String::from("Only the group's owners can do that"))),
+        None => Err(ApiError::NotFound(String::from("Group not found")))
+    }
+}
+
+// Checks that the user owns the group
+async fn owned_group(conn: &mut PgConnection, group_id: i32, user_id: i32) -> Result<(), ApiError> {
+    check_owner(member_role(conn, group_id, user_id).await?)
+}
+
+// Whoever owns a personal group stays its owner
+fn check_role_change(personal_for: Option<i32>, user_id: i32, role: &GroupRole) -> Result<(), ApiError> {
+    if personal_for == Some(user_id) && *role != GroupRole::Owner {
+        return Err(ApiError::BadRequest(String::from("A personal group's owner is always an owner")));
+    }
+
+    Ok(())
+}
+
+// Whether someone whose role in the group is `role` can take `user_id` out
+// of it. Owners can remove anyone and members only themselves, but no one
+// leaves their own personal group.
+fn check_removal(role: Option<GroupRole>, by: i32, user_id: i32, personal_for: Option<i32>) -> Result<(), ApiError> {
+    if by == user_id {
+        role.ok_or_else(|| ApiError::NotFound(String::from("Group not found")))?;
+    }
+    else {
+        check_owner(role)?;
+    }
+
+    if personal_for == Some(user_id) {
+        return Err(ApiError::BadRequest(String::from("No one can leave their own personal group")));
+    }
+
+    Ok(())
+}
+
+fn clean_name(name: &str) -> Result<&str, ApiError> {
+    let name = name.trim();
+    if name.is_empty() {
+        Err(ApiError::BadRequest(String::from("Group names can't be empty")))
+    }
+    else {
+        Ok(name)
+    }
+}
+
+/// The groups the user is in, their personal group first
+#[axum::debug_handler]
+pub async fn list_groups(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut conn = pool.acquire().await?;
+    personal_group(&mut conn, user.id()).await?;
+
+    let groups: Vec<GroupResponse> = sqlx::query_as("
+            SELECT Groups.id, Groups.name, Groups.personal_for IS NOT DISTINCT FROM $1 AS personal, GroupMembers.role
+            FROM Groups
+                JOIN GroupMembers ON GroupMembers.group_id = Groups.id
+            WHERE GroupMembers.user_id = $1
+            ORDER BY personal DESC, lower(Groups.name), Groups.id")
+        .bind(user.id())
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(Json(groups))
+}
+
+#[axum::debug_handler]
+pub async fn create_group(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Json(group_data): Json<GroupData>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let name = clean_name(&group_data.name)?;
+
+    let mut tx = pool.begin().await?;
+
+    let group_id: i32 = sqlx::query_scalar("INSERT INTO Groups (name) VALUES ($1) RETURNING id")
+        .bind(name)
+        .fetch_one(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO GroupMembers (group_id, user_id, role) VALUES ($1, $2, 'owner')")
+        .bind(group_id)
+        .bind(user.id())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    println!("Created group {}", group_id);
+
+    Ok(Json(
+        GroupResponse {
+            id: group_id,
+            name: name.to_string(),
+            personal: false,
+            role: String::from(GroupRole::Owner.as_str())
+        }
+    ))
+}
+
+/// A group and everyone in it
+#[axum::debug_handler]
+pub async fn group(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(group_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut conn = pool.acquire().await?;
+    let role = member_role(&mut conn, group_id, user.id()).await?
+        .ok_or_else(|| ApiError::NotFound(String::from("Group not found")))?;
+
+    let (name, personal_for): (String, Option<i32>) = sqlx::query_as("SELECT name, personal_for FROM Groups WHERE id = $1")
+        .bind(group_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let members: Vec<GroupMember> = sqlx::query_as("
+            SELECT Users.id AS user_id, Users.username, GroupMembers.role
+            FROM GroupMembers
+                JOIN Users ON Users.id = GroupMembers.user_id
+            WHERE GroupMembers.group_id = $1
+            ORDER BY GroupMembers.role DESC, lower(Users.username)")
+        .bind(group_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(Json(
+        GroupDetails {
+            id: group_id,
+            name,
+            personal: personal_for == Some(user.id()),
+            role: String::from(role.as_str()),
+            members
+        }
+    ))
+}
+
+#[axum::debug_handler]
+pub async fn update_group(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(group_id): Path<i32>,
+    Json(group_data): Json<GroupData>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let name = clean_name(&group_data.name)?;
+
+    let mut conn = pool.acquire().await?;
+    owned_group(&mut conn, group_id, user.id()).await?;
+
+    sqlx::query("UPDATE Groups SET name = $1 WHERE id = $2")
+        .bind(name)
+        .bind(group_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Renamed group successfully"),
+            success: true
+        }
+    ))
+}
+
+/// Deletes a group along with its games and players. Personal groups can't
+/// be deleted.
+#[axum::debug_handler]
+pub async fn delete_group(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(group_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+    owned_group(&mut tx, group_id, user.id()).await?;
+
+    // The games' entries, turns, minigames and ratings all go with them
+    // through ON DELETE CASCADE
+    let result = sqlx::query("DELETE FROM Groups WHERE id = $1 AND personal_for IS NULL")
+        .bind(group_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::BadRequest(String::from("Personal groups can't be deleted")));
+    }
+
+    tx.commit().await?;
+
+    println!("Deleted group {}", group_id);
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Deleted group successfully"),
+            success: true
+        }
+    ))
+}
+
+/// Changes a member's role. A group always keeps at least one owner.
+#[axum::debug_handler]
+pub async fn update_member(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path((group_id, user_id)): Path<(i32, i32)>,
+    Json(member): Json<MemberRole>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+    owned_group(&mut tx, group_id, user.id()).await?;
+
+    let personal_for: Option<i32> = sqlx::query_scalar("SELECT personal_for FROM Groups WHERE id = $1")
+        .bind(group_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    check_role_change(personal_for, user_id, &member.role)?;
+
+    let result = sqlx::query("UPDATE GroupMembers SET role = $1 WHERE group_id = $2 AND user_id = $3")
+        .bind(member.role.as_str())
+        .bind(group_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(String::from("That user isn't in the group")));
+    }
+
+    let owners: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM GroupMembers WHERE group_id = $1 AND role = 'owner'")
+        .bind(group_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if owners == 0 {
+        return Err(ApiError::BadRequest(String::from("The group needs another owner first")));
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Changed role successfully"),
+            success: true
+        }
+    ))
+}
+
+/// Removes someone from a group. Owners can remove anyone, and anyone can
+/// leave, except the last owner and the owner of a personal group. The
+/// games they added stay in the group.
+#[axum::debug_handler]
+pub async fn remove_member(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path((group_id, user_id)): Path<(i32, i32)>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+    let role = member_role(&mut tx, group_id, user.id()).await?;
+    let personal_for: Option<i32> = sqlx::query_scalar("SELECT personal_for FROM Groups WHERE id = $1")
+        .bind(group_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .flatten();
+    check_removal(role, user.id(), user_id, personal_for)?;
+
+    let result = sqlx::query("DELETE FROM GroupMembers WHERE group_id = $1 AND user_id = $2")
+        .bind(group_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(String::from("That user isn't in the group")));
+    }
+
+    let owners: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM GroupMembers WHERE group_id = $1 AND role = 'owner'")
+        .bind(group_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if owners == 0 {
+        return Err(ApiError::BadRequest(String::from("The group needs another owner first")));
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Removed member successfully"),
+            success: true
+        }
+    ))
+}
+
+const INVITATIONS_QUERY: &str = "
+    SELECT
+        GroupInvitations.id,
+        Groups.id AS group_id,
+        Groups.name AS group_name,
+        Invited.username,
+        Inviter.username AS invited_by,
+        GroupInvitations.created_at
+    FROM GroupInvitations
+        JOIN Groups ON Groups.id = GroupInvitations.group_id
+        JOIN Users Invited ON Invited.id = GroupInvitations.user_id
+        LEFT JOIN Users Inviter ON Inviter.id = GroupInvitations.invited_by";
+
+/// Invites a user to a group by their username
+#[axum::debug_handler]
+pub async fn invite(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(group_id): Path<i32>,
+    Json(invitation): Json<InvitationData>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+    owned_group(&mut tx, group_id, user.id()).await?;
+
+    let username = invitation.username.trim();
+    let invitee: i32 = sqlx::query_scalar("SELECT id FROM Users WHERE username = $1")
+        .bind(username)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("There's no user called {username}")))?;
+
+    if member_role(&mut tx, group_id, invitee).await?.is_some() {
+        return Err(ApiError::Conflict(format!("{username} is already in the group")));
+    }
+
+    let invitation_id: Option<i32> = sqlx::query_scalar("
+            INSERT INTO GroupInvitations (group_id, user_id, invited_by) VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            RETURNING id")
+        .bind(group_id)
+        .bind(invitee)
+        .bind(user.id())
+        .fetch_optional(&mut *tx)
+        .await?;
+    let invitation_id = invitation_id.ok_or_else(|| ApiError::Conflict(format!("{username} has already been invited")))?;
+
+    let invitation: InvitationResponse = sqlx::query_as(&format!("{INVITATIONS_QUERY} WHERE GroupInvitations.id = $1"))
+        .bind(invitation_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(invitation))
+}
+
+/// The invitations to a group nobody has answered yet
+#[axum::debug_handler]
+pub async fn group_invitations(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(group_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut conn = pool.acquire().await?;
+    owned_group(&mut conn, group_id, user.id()).await?;
+
+    let invitations: Vec<InvitationResponse> = sqlx::query_as(&format!("
+            {INVITATIONS_QUERY}
+            WHERE GroupInvitations.group_id = $1
+            ORDER BY GroupInvitations.created_at"))
+        .bind(group_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(Json(invitations))
+}
+
+/// The invitations the user has been sent
+#[axum::debug_handler]
+pub async fn my_invitations(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let invitations: Vec<InvitationResponse> = sqlx::query_as(&format!("
+            {INVITATIONS_QUERY}
+            WHERE GroupInvitations.user_id = $1
+            ORDER BY GroupInvitations.created_at"))
+        .bind(user.id())
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(invitations))
+}
+
+/// Joins the group an invitation is for, as a member
+#[axum::debug_handler]
+pub async fn accept_invitation(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(invitation_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+
+    let group_id: i32 = sqlx::query_scalar("DELETE FROM GroupInvitations WHERE id = $1 AND user_id = $2 RETURNING group_id")
+        .bind(invitation_id)
+        .bind(user.id())
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(String::from("Invitation not found")))?;
+
+    sqlx::query("INSERT INTO GroupMembers (group_id, user_id, role) VALUES ($1, $2, 'member') ON CONFLICT DO NOTHING")
+        .bind(group_id)
+        .bind(user.id())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Joined group successfully"),
+            success: true
+        }
+    ))
+}
+
+/// Declines an invitation, or takes it back if it's an owner of the group
+/// doing it
+#[axum::debug_handler]
+pub async fn delete_invitation(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(invitation_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let result = sqlx::query("
+            DELETE FROM GroupInvitations
+            WHERE id = $1 AND (
+                user_id = $2
+                OR group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $2 AND role = 'owner')
+            )")
+        .bind(invitation_id)
+        .bind(user.id())
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(String::from("Invitation not found")));
+    }
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Deleted invitation successfully"),
+            success: true
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_owners_manage_a_group() {
+        assert!(check_owner(Some(GroupRole::Owner)).is_ok());
+        assert!(matches!(check_owner(Some(GroupRole::Member)), Err(ApiError::Forbidden(_))));
+        assert!(matches!(check_owner(None), Err(ApiError::NotFound(_))));
+    }
+
+    #[test]
+    fn personal_group_owner_stays_an_owner() {
+        assert!(check_role_change(Some(1), 1, &GroupRole::Owner).is_ok());
+        assert!(matches!(check_role_change(Some(1), 1, &GroupRole::Member), Err(ApiError::BadRequest(_))));
+        assert!(check_role_change(Some(1), 2, &GroupRole::Member).is_ok());
+        assert!(check_role_change(None, 1, &GroupRole::Member).is_ok());
+    }
+
+    #[test]
+    fn anyone_can_leave() {
+        assert!(check_removal(Some(GroupRole::Member), 2, 2, None).is_ok());
+        assert!(check_removal(Some(GroupRole::Owner), 2, 2, Some(1)).is_ok());
+        assert!(matches!(check_removal(None, 2, 2, None), Err(ApiError::NotFound(_))));
+    }
+
+    #[test]
+    fn only_owners_remove_others() {
+        assert!(check_removal(Some(GroupRole::Owner), 1, 2, None).is_ok());
+        assert!(matches!(check_removal(Some(GroupRole::Member), 1, 2, None), Err(ApiError::Forbidden(_))));
+        assert!(matches!(check_removal(None, 1, 2, None), Err(ApiError::NotFound(_))));
+    }
+
+    #[test]
+    fn no_one_leaves_their_personal_group() {
+        assert!(matches!(check_removal(Some(GroupRole::Owner), 1, 1, Some(1)), Err(ApiError::BadRequest(_))));
+        assert!(matches!(check_removal(Some(GroupRole::Owner), 2, 1, Some(1)), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn group_names_are_trimmed() {
+        assert_eq!(clean_name("  Friday night  ").unwrap(), "Friday night");
+        assert!(matches!(clean_name("   "), Err(ApiError::BadRequest(_))));
+    }
+}
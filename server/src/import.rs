@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use sqlx::{Acquire, Postgres, Transaction};
+use sqlx::postgres::PgPool;
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::Query,
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json
+};
+use crate::listfields::Field;
+use crate::requests::{GameData, GroupQuery, ImportFormat, ImportQuery};
+use crate::responses::{ImportRecord, ImportReport};
+use crate::errors::ApiError;
+use crate::requests::titles;
+use crate::ratings::recompute_from;
+use crate::routes::{AuthSession, check_game, insert_game, signed_in_user};
+use crate::groups::member_group;
+use crate::export::GAME_COLUMNS;
+
+// A game read out of the file, or why it couldn't be
+struct ParsedGame {
+    line: usize,
+    game: Option<String>,
+    data: Result<GameData, String>
+}
+
+fn format_from(headers: &HeaderMap) -> Result<ImportFormat, ApiError> {
+    let content_type = headers.get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+
+    if content_type.contains("csv") {
+        Ok(ImportFormat::Csv)
+    }
+    else if content_type.contains("json") {
+        Ok(ImportFormat::Jsonl)
+    }
+    else {
+        Err(ApiError::BadRequest(String::from("Say whether the file is CSV or JSON Lines with ?format=csv or ?format=jsonl")))
+    }
+}
+
+// Every non-blank line is a GameData
+fn parse_jsonl(body: &str) -> Vec<ParsedGame> {
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let game = serde_json::from_str::<Value>(line).ok()
+                .and_then(|value| value.get("game")?.as_str().map(String::from));
+
+            ParsedGame {
+                line: i + 1,
+                game,
+                data: serde_json::from_str(line).map_err(|err| err.to_string())
+            }
+        })
+        .collect()
+}
+
+// A cell as the JSON the field would have in a GameData. Anything that
+// doesn't parse as the field's type is left as a string for serde to reject.
+fn scalar_value(ty: &str, cell: &str) -> Value {
+    let parsed = match ty {
+        "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" => cell.parse::<i64>().ok().map(Value::from),
+        "f32" | "f64" => cell.parse::<f64>().ok().map(Value::from),
+        "bool" => cell.parse::<bool>().ok().map(Value::from),
+        _ => None
+    };
+
+    parsed.unwrap_or_else(|| Value::from(cell))
+}
+
+fn cell_value(field: &Field, cell: &str) -> Value {
+    let cell = cell.trim();
+    if field.list {
+        // Lists are joined with semicolons, like in the export
+        let items = cell.split(';')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| scalar_value(&field.ty, item))
+            .collect();
+        Value::Array(items)
+    }
+    else if cell.is_empty() && field.optional {
+        Value::Null
+    }
+    else {
+        scalar_value(&field.ty, cell)
+    }
+}
+
+/// Turns the rows for one game into a GameData by way of the JSON the API
+/// takes, so it goes through the same serde types as everything else.
+fn csv_game(columns: &HashMap<&str, usize>, rows: &[csv::StringRecord], titles: &[(String, Vec<Field>)]) -> Result<GameData, String> {
+    let cell = |row: &csv::StringRecord, name: &str| {
+        columns.get(name).and_then(|i| row.get(*i)).unwrap_or("").trim().to_string()
+    };
+
+    let game = cell(&rows[0], "game");
+    let (_, fields) = titles.iter()
+        .find(|(name, _)| *name == game)
+        .ok_or_else(|| format!("\"{}\" is not a game", game))?;
+
+    let player_data: Vec<Value> = rows.iter().map(|row| {
+        let mut entry = Map::new();
+        // player_id is the player in the group the file was exported from,
+        // which means nothing here, so players are found by name
+        for field in fields.iter().filter(|field| field.name != "player_id") {
+            if let Some(value) = columns.get(field.name.as_str()).and_then(|i| row.get(*i)) {
+                entry.insert(field.name.clone(), cell_value(field, value));
+            }
+        }
+        Value::Object(entry)
+    }).collect();
+
+    let value = json!({
+        "game": game,
+        "board": cell(&rows[0], "board"),
+        "turns": scalar_value("i32", &cell(&rows[0], "turns")),
+        "date": cell(&rows[0], "date"),
+        "player_data": player_data
+    });
+
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
+
+/// The layout of /api/export.csv: a row per player, with the game's columns
+/// repeated on each one. Rows next to each other with the same game_id are
+/// one game. Without a game_id, rows with the same game, board, turns and
+/// date are. Any player_id column is ignored, see csv_game.
+fn parse_csv(body: &str) -> Result<Vec<ParsedGame>, ApiError> {
+    let mut reader = csv::Reader::from_reader(body.as_bytes());
+    let headers = reader.headers()
+        .map_err(|err| ApiError::BadRequest(format!("Couldn't read the CSV header: {err}")))?
+        .clone();
+    let columns: HashMap<&str, usize> = headers.iter().enumerate().map(|(i, name)| (name.trim(), i)).collect();
+    if !columns.contains_key("game") {
+        return Err(ApiError::BadRequest(String::from("The CSV needs a game column")));
+    }
+
+    let titles = titles();
+    let key = |row: &csv::StringRecord| {
+        let cells: Vec<&str> = GAME_COLUMNS.iter()
+            .map(|name| columns.get(name).and_then(|i| row.get(*i)).unwrap_or("").trim())
+            .collect();
+        match cells[0] {
+            "" => cells[1..].join("\n"),
+            game_id => game_id.to_string()
+        }
+    };
+
+    let mut parsed = Vec::new();
+    let mut group: Vec<csv::StringRecord> = Vec::new();
+    let flush = |group: &mut Vec<csv::StringRecord>, parsed: &mut Vec<ParsedGame>| {
+        if let Some(first) = group.first() {
+            parsed.push(ParsedGame {
+                line: first.position().map(|position| position.line() as usize).unwrap_or(0),
+                game: columns.get("game").and_then(|i| first.get(*i)).map(|game| game.trim().to_string()),
+                data: csv_game(&columns, group, &titles)
+            });
+        }
+        group.clear();
+    };
+
+    for row in reader.records() {
+        match row {
+            Ok(row) => {
+                if group.first().is_some_and(|first| key(first) != key(&row)) {
+                    flush(&mut group, &mut parsed);
+                }
+                group.push(row);
+            },
+            Err(err) => {
+                flush(&mut group, &mut parsed);
+                parsed.push(ParsedGame {
+                    line: err.position().map(|position| position.line() as usize).unwrap_or(0),
+                    game: None,
+                    data: Err(err.to_string())
+                });
+            }
+        }
+    }
+    flush(&mut group, &mut parsed);
+
+    Ok(parsed)
+}
+
+// Ok(Err(reason)) if the game was rejected. Err is only for things going
+// wrong on our end, which stop the whole import.
+async fn import_game(tx: &mut Transaction<'_, Postgres>, user_id: i32, group_id: i32, data: GameData) -> Result<Result<i32, String>, ApiError> {
+    if let Err(err) = check_game(&data) {
+        return Ok(Err(err.to_string()));
+    }
+
+    // A savepoint, so a game that fails to insert doesn't take the others with it
+    let mut savepoint = tx.begin().await?;
+    match insert_game(&mut savepoint, user_id, group_id, data).await {
+        Ok(game_id) => {
+            savepoint.commit().await?;
+            Ok(Ok(game_id))
+        },
+        Err(err) => {
+            savepoint.rollback().await?;
+            match err.status_and_message() {
+                (status, message) if status.is_client_error() => Ok(Err(message)),
+                _ => Err(err)
+            }
+        }
+    }
+}
+
+/// Imports games from CSV, laid out like /api/export.csv, or JSON Lines of
+/// GameData. Every game is checked like POST /api/games would, and the
+/// accepted ones go in together in one transaction. With dry_run the
+/// transaction is rolled back, so the report is exactly what would happen.
+#[axum::debug_handler]
+pub async fn import(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<ImportQuery>,
+    headers: HeaderMap,
+    body: String
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    let format = match params.format {
+        Some(format) => format,
+        None => format_from(&headers)?
+    };
+    let parsed = match format {
+        ImportFormat::Csv => parse_csv(&body)?,
+        ImportFormat::Jsonl => parse_jsonl(&body)
+    };
+
+    let mut tx = pool.begin().await?;
+
+    let mut records = Vec::new();
+    let mut earliest = None;
+    for ParsedGame { line, game, data } in parsed {
+        let result = match data {
+            Ok(data) => {
+                let date = data.date;
+                let result = import_game(&mut tx, user.id(), group_id, data).await?;
+                if result.is_ok() {
+                    earliest = Some(earliest.map_or(date, |earliest: DateTime<Utc>| earliest.min(date)));
+                }
+                result
+            },
+            Err(reason) => Err(reason)
+        };
+
+        records.push(ImportRecord {
+            line,
+            game,
+            accepted: result.is_ok(),
+            game_id: result.as_ref().ok().copied().filter(|_| !params.dry_run),
+            reason: result.err()
+        });
+    }
+
+    if params.dry_run {
+        tx.rollback().await?;
+    }
+    else {
+        // Rated once at the end, rather than replaying after every old game
+        if let Some(earliest) = earliest {
+            recompute_from(&mut tx, group_id, earliest).await?;
+        }
+        tx.commit().await?;
+    }
+
+    let accepted = records.iter().filter(|record| record.accepted).count();
+    println!("Imported {} of {} games{}", accepted, records.len(), if params.dry_run { " (dry run)" } else { "" });
+
+    Ok(Json(
+        ImportReport {
+            dry_run: params.dry_run,
+            accepted,
+            rejected: records.len() - accepted,
+            records
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::requests::{BonusStar, MarioPartyData};
+
+    // The header /api/export.csv writes for Mario Party 2
+    fn header() -> String {
+        let (_, fields) = titles().into_iter().find(|(game, _)| game == "Mario Party 2").unwrap();
+        String::from_utf8(crate::export::header_line(&fields).unwrap()).unwrap().trim_end().to_string()
+    }
+
+    fn row(game_id: &str, player_id: &str, player: &str, placement: i32, bonus_stars: &str) -> String {
+        format!("{game_id},Mario Party 2,Western Land,20,2025-01-01T00:00:00Z,{player_id},{player},Mario,3,40,20,60,10,4,2,1,1,1,1,0,{placement},{bonus_stars}")
+    }
+
+    #[test]
+    fn jsonl_skips_blank_lines() {
+        let line = r#"{"game":"Mario Party 2","board":"Western Land","turns":20,"date":"2025-01-01T00:00:00Z","player_data":[]}"#;
+        let parsed = parse_jsonl(&format!("{line}\n\n  \n{line}\n"));
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].line, 1);
+        assert_eq!(parsed[1].line, 4);
+        assert_eq!(parsed[1].game.as_deref(), Some("Mario Party 2"));
+        assert!(parsed[1].data.is_ok());
+    }
+
+    #[test]
+    fn jsonl_keeps_going_after_a_bad_line() {
+        let parsed = parse_jsonl("{\"game\":\"Mario Party 2\"}\nnot json\n");
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].game.as_deref(), Some("Mario Party 2"));
+        assert!(parsed[0].data.is_err());
+        assert_eq!(parsed[1].game, None);
+        assert!(parsed[1].data.is_err());
+    }
+
+    #[test]
+    fn csv_groups_rows_by_game_id() {
+        let body = [
+            header(),
+            row("1", "7", "Ann", 1, "Minigame Star;Coin Star"),
+            row("1", "8", "Bob", 2, ""),
+            row("2", "7", "Ann", 1, "")
+        ].join("\n");
+        let parsed = parse_csv(&body).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].line, 2);
+        assert_eq!(parsed[1].line, 4);
+
+        let data = parsed[0].data.as_ref().unwrap();
+        assert_eq!(data.turns, 20);
+        let MarioPartyData::MarioParty2(players) = &data.player_data else {
+            panic!("expected Mario Party 2, got {:?}", data.player_data);
+        };
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].player_name.as_deref(), Some("Ann"));
+        // The exported ids are from another group, Ann is found by name
+        assert_eq!(players[0].player_id, None);
+        assert_eq!(players[0].bonus_stars, vec![BonusStar::MinigameStar, BonusStar::CoinStar]);
+        assert!(players[1].bonus_stars.is_empty());
+    }
+
+    #[test]
+    fn csv_without_game_ids_groups_by_game() {
+        let body = [header(), row("", "7", "Ann", 1, ""), row("", "8", "Bob", 2, "")].join("\n");
+        let parsed = parse_csv(&body).unwrap();
+
+        assert_eq!(parsed.len(), 1);
+        assert!(parsed[0].data.is_ok());
+    }
+
+    #[test]
+    fn csv_rejects_unknown_games_and_bad_cells() {
+        let body = [
+            header(),
+            row("1", "7", "Ann", 1, "").replace("Mario Party 2", "Mario Party 99"),
+            row("2", "7", "Ann", 1, "").replace(",40,", ",lots,")
+        ].join("\n");
+        let parsed = parse_csv(&body).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].data.as_ref().unwrap_err(), "\"Mario Party 99\" is not a game");
+        assert!(parsed[1].data.is_err());
+    }
+
+    #[test]
+    fn csv_needs_a_game_column() {
+        assert!(matches!(parse_csv("board,turns\nWestern Land,20"), Err(ApiError::BadRequest(_))));
+    }
+}
@@ -1,6 +1,9 @@
 use serde::Serialize;
 
-#[derive(Debug)]
+// Serialized untagged, so an enum is {"name": ..., "variants": [...]} and a
+// struct is just its list of fields
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
 pub enum ObjectData {
     EnumData(EnumData),
     Fields(Vec<Field>)
@@ -8,23 +11,53 @@ pub enum ObjectData {
 
 pub trait ListFields {
     fn list_fields() -> ObjectData;
+
+    /// Checks the limits set with #[listfields(min = .., max = .., per_turn)].
+    /// `turns` is how many turns the game lasted, which is the limit for
+    /// per_turn fields.
+    fn validate(&self, turns: i32) -> Result<(), String>;
 }
 
 #[derive(Serialize, Debug, Clone)]
 pub struct Field {
     pub name: String,
-    pub ty: String
+    pub ty: String,
+    // True for a Vec of `ty`
+    pub list: bool,
+    // True for an Option of `ty`, which can be left out
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    // What `ty` looks like, if it's one of our types, like the variants
+    // of a *Characters enum
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_data: Option<ObjectData>,
+    // The limits from #[listfields(...)], on the length for lists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+    // Can't be more than the game's number of turns
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub per_turn: bool
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Debug, Clone)]
 pub struct EnumData {
     pub name: String,
     pub variants: Vec<Variant>
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Debug, Clone)]
 pub struct Variant {
+    // The name it's serialized as, so serde renames are already applied
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ty: Option<String>,
-    pub type_data: Option<ObjectData>
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_data: Option<ObjectData>,
+    // Limits on how many items a variant holding a list can have
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>
 }
@@ -0,0 +1,567 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPool;
+use tokio::sync::broadcast;
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::{Path, Query, ws::{Message, WebSocket, WebSocketUpgrade}},
+    response::IntoResponse,
+    Json
+};
+use crate::listfields::{EnumData, Field, ListFields, ObjectData};
+use crate::requests::{GameData, GroupQuery, LiveCommand, LiveGameStart, MarioPartyData};
+use crate::responses::{LiveGameResponse, LiveMessage, MessageResponse};
+use crate::errors::ApiError;
+use crate::requests::titles;
+use crate::ratings::recompute_from;
+use crate::players::resolve_players;
+use crate::permissions::{has_permission, Permission};
+use crate::routes::{AuthSession, check_game, insert_player_data, owned_game, signed_in_user};
+use crate::groups::{member_group, member_role};
+
+// The fields of a game's state that aren't in player_data and can be changed
+const GAME_FIELDS: [&str; 3] = ["board", "turns", "date"];
+
+/// A game being played right now. The state lives here while anyone is
+/// connected, and is saved to the game's live_state after every change so
+/// nothing is lost if the server restarts.
+pub struct LiveGame {
+    id: i32,
+    group_id: i32,
+    // The fields of the game's entry struct
+    fields: Vec<Field>,
+    state: tokio::sync::Mutex<Value>,
+    updates: broadcast::Sender<LiveMessage>,
+    // When someone last opened it or changed it
+    last_used: Mutex<Instant>
+}
+
+/// Every live game someone has opened recently, by game id. Games nobody is
+/// watching are dropped once they've been idle for a while, they're loaded
+/// again from live_state the next time someone opens them.
+#[derive(Clone, Default)]
+pub struct LiveGames(Arc<Mutex<HashMap<i32, Arc<LiveGame>>>>);
+
+fn is_integer(ty: &str) -> bool {
+    matches!(ty, "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64")
+}
+
+fn title_fields(game: &str) -> Option<Vec<Field>> {
+    titles().into_iter().find(|(name, _)| name == game).map(|(_, fields)| fields)
+}
+
+impl LiveGames {
+    /// The live game with `game_id`, loading it from the database if nobody
+    /// has opened it yet. Anyone in the game's group can play along.
+    async fn get(&self, pool: &PgPool, game_id: i32, user_id: i32) -> Result<Arc<LiveGame>, ApiError> {
+        let not_found = || ApiError::NotFound(String::from("There's no game being played with that id"));
+
+        let loaded = self.0.lock().unwrap().get(&game_id).cloned();
+        if let Some(game) = loaded {
+            let mut conn = pool.acquire().await?;
+            return match member_role(&mut conn, game.group_id, user_id).await? {
+                Some(_) => {
+                    game.touch();
+                    Ok(game)
+                },
+                None => Err(not_found())
+            };
+        }
+
+        let row: Option<(String, String, i32)> = sqlx::query_as("
+                SELECT game, live_state, group_id FROM Games
+                WHERE id = $1 AND draft AND live_state IS NOT NULL
+                    AND group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $2)")
+            .bind(game_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+        let (game, state, group_id) = row.ok_or_else(not_found)?;
+
+        let state: Value = serde_json::from_str(&state).map_err(|err| ApiError::Internal(err.to_string()))?;
+        let fields = title_fields(&game).ok_or_else(not_found)?;
+        let (updates, _) = broadcast::channel(64);
+
+        // Someone else might have loaded it while we were, theirs wins
+        let mut games = self.0.lock().unwrap();
+        let game = games.entry(game_id).or_insert_with(|| Arc::new(LiveGame {
+            id: game_id,
+            group_id,
+            fields,
+            state: tokio::sync::Mutex::new(state),
+            updates,
+            last_used: Mutex::new(Instant::now())
+        }));
+        game.touch();
+
+        Ok(game.clone())
+    }
+
+    fn remove(&self, game_id: i32) -> Option<Arc<LiveGame>> {
+        self.0.lock().unwrap().remove(&game_id)
+    }
+
+    // Drops the games nobody is watching that haven't been used in `idle`
+    fn drop_idle(&self, idle: Duration) {
+        let mut games = self.0.lock().unwrap();
+        let before = games.len();
+        games.retain(|_, game| {
+            game.updates.receiver_count() > 0 || game.last_used.lock().unwrap().elapsed() < idle
+        });
+        if games.len() < before {
+            println!("Dropped {} idle live games", before - games.len());
+        }
+    }
+
+    /// Drops idle games every `idle`, for as long as the server runs
+    pub async fn continuously_drop_idle(self, idle: Duration) {
+        let mut interval = tokio::time::interval(idle);
+        loop {
+            interval.tick().await;
+            self.drop_idle(idle);
+        }
+    }
+}
+
+impl LiveGame {
+    fn touch(&self) {
+        *self.last_used.lock().unwrap() = Instant::now();
+    }
+
+    fn field(&self, name: &str) -> Result<&Field, String> {
+        self.fields.iter()
+            .find(|field| field.name == name)
+            .ok_or_else(|| format!("{} isn't something players have in this game", name))
+    }
+
+    // Applies a command to a copy of the state, so a bad one changes nothing
+    fn apply(&self, state: &Value, command: LiveCommand) -> Result<Value, String> {
+        let mut state = state.clone();
+        let player_count = state["player_data"].as_array().map_or(0, Vec::len);
+        let check_player = |player: usize| {
+            if player < player_count { Ok(player) } else { Err(format!("There's no player {}", player)) }
+        };
+
+        match command {
+            LiveCommand::Delta { player, changes } => {
+                let player = check_player(player)?;
+                for (name, change) in changes {
+                    let field = self.field(&name)?;
+                    if field.list || !is_integer(&field.ty) {
+                        return Err(format!("{} isn't a number", name));
+                    }
+
+                    // Every number ends up in an INTEGER column, so that's as far as they go
+                    let value = state["player_data"][player][&name].as_i64().unwrap_or(0)
+                        .checked_add(change)
+                        .filter(|value| i32::try_from(*value).is_ok())
+                        .ok_or_else(|| format!("{} can't change by {}", name, change))?;
+                    if let Some(min) = field.min.filter(|min| value < *min) {
+                        return Err(format!("{} can't go below {}", name, min));
+                    }
+                    if let Some(max) = field.max.filter(|max| value > *max) {
+                        return Err(format!("{} can't go above {}", name, max));
+                    }
+                    state["player_data"][player][&name] = json!(value);
+                }
+            },
+            LiveCommand::Set { player: Some(player), field, value } => {
+                let player = check_player(player)?;
+                self.field(&field)?;
+                state["player_data"][player][&field] = value;
+            },
+            LiveCommand::Set { player: None, field, value } => {
+                if !GAME_FIELDS.contains(&field.as_str()) {
+                    return Err(format!("The game's {} can't be changed", field));
+                }
+                state[&field] = value;
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Runs a command from a websocket, saves the new state and sends it to
+    /// everyone watching. The error is for whoever sent the command.
+    async fn handle(&self, pool: &PgPool, text: &str) -> Result<(), String> {
+        let command: LiveCommand = serde_json::from_str(text).map_err(|err| err.to_string())?;
+
+        let mut state = self.state.lock().await;
+        let new_state = self.apply(&state, command)?;
+
+        let result = sqlx::query("UPDATE Games SET live_state = $1 WHERE id = $2 AND draft")
+            .bind(new_state.to_string())
+            .bind(self.id)
+            .execute(pool)
+            .await
+            .map_err(|err| {
+                println!("Failed to save live game {}: {err}", self.id);
+                String::from("Server error")
+            })?;
+        if result.rows_affected() == 0 {
+            return Err(String::from("This game isn't being played anymore"));
+        }
+
+        *state = new_state;
+        self.touch();
+        // No one listening isn't an error
+        let _ = self.updates.send(LiveMessage::State { game_id: self.id, state: state.clone() });
+
+        Ok(())
+    }
+}
+
+// A new player's entry, with every number that wasn't given at 0 and every
+// list empty
+fn new_entry(fields: &[Field], mut entry: serde_json::Map<String, Value>) -> Value {
+    for field in fields {
+        if entry.contains_key(&field.name) {
+            continue;
+        }
+
+        if field.list {
+            entry.insert(field.name.clone(), json!([]));
+        }
+        else if field.optional {
+            entry.insert(field.name.clone(), Value::Null);
+        }
+        else if is_integer(&field.ty) {
+            entry.insert(field.name.clone(), json!(0));
+        }
+    }
+
+    Value::Object(entry)
+}
+
+/// Starts a live game, as a draft in Games with no entries
+#[axum::debug_handler]
+pub async fn start_live_game(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Json(start): Json<LiveGameStart>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    let not_a_game = || ApiError::BadRequest(format!("\"{}\" is not a game", start.game));
+    let fields = title_fields(&start.game).ok_or_else(not_a_game)?;
+
+    // The same player limits as a finished game
+    if let ObjectData::EnumData(EnumData { name: _, variants }) = MarioPartyData::list_fields() {
+        let variant = variants.iter().find(|variant| variant.name == start.game).ok_or_else(not_a_game)?;
+        let count = start.players.len() as i64;
+        if variant.min.is_some_and(|min| count < min) || variant.max.is_some_and(|max| count > max) {
+            return Err(ApiError::BadRequest(format!(
+                "{} needs between {} and {} players",
+                start.game, variant.min.unwrap_or(1), variant.max.unwrap_or(count)
+            )));
+        }
+    }
+
+    // The same board and turn checks as a finished game, so a game can't be
+    // played all the way through only to be turned away at the end
+    let title: MarioPartyData = serde_json::from_value(json!({ "game": &start.game, "player_data": [] }))
+        .map_err(|_| not_a_game())?;
+    if !title.is_valid_board(&start.board) {
+        return Err(ApiError::BadRequest(format!("\"{}\" is not a board in this game", start.board)));
+    }
+    if let ObjectData::Fields(fields) = GameData::list_fields() {
+        if let Some(turns) = fields.iter().find(|field| field.name == "turns") {
+            let count = start.turns as i64;
+            if let Some(min) = turns.min.filter(|min| count < *min) {
+                return Err(ApiError::BadRequest(format!("turns can't be less than {}, got {}", min, count)));
+            }
+            if let Some(max) = turns.max.filter(|max| count > *max) {
+                return Err(ApiError::BadRequest(format!("turns can't be more than {}, got {}", max, count)));
+            }
+        }
+    }
+
+    let date = start.date.unwrap_or_else(Utc::now);
+    let player_data: Vec<Value> = start.players.into_iter().map(|player| new_entry(&fields, player)).collect();
+    let state = json!({
+        "game": start.game,
+        "board": start.board,
+        "turns": start.turns,
+        "date": date,
+        "player_data": player_data
+    });
+
+    let game_id: i32 = sqlx::query_scalar("
+            INSERT INTO Games (user_id, group_id, date, turns, board, game, draft, live_state)
+            VALUES ($1, $2, $3, $4, $5, $6, true, $7)
+            RETURNING id")
+        .bind(user.id())
+        .bind(group_id)
+        .bind(date)
+        .bind(start.turns)
+        .bind(&start.board)
+        .bind(&start.game)
+        .bind(state.to_string())
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(Json(LiveGameResponse { game_id, state }))
+}
+
+/// Every game in the group that isn't finished yet
+#[axum::debug_handler]
+pub async fn list_live_games(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    let rows: Vec<(i32, String)> = sqlx::query_as("
+            SELECT id, live_state FROM Games
+            WHERE group_id = $1 AND draft AND live_state IS NOT NULL
+            ORDER BY date DESC, id DESC")
+        .bind(group_id)
+        .fetch_all(&pool)
+        .await?;
+
+    let games: Vec<LiveGameResponse> = rows.into_iter().filter_map(|(game_id, state)| {
+        Some(LiveGameResponse { game_id, state: serde_json::from_str(&state).ok()? })
+    }).collect();
+
+    Ok(Json(games))
+}
+
+#[axum::debug_handler]
+pub async fn live_game(
+    Extension(pool): Extension<PgPool>,
+    Extension(live_games): Extension<LiveGames>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let game = live_games.get(&pool, game_id, user.id()).await?;
+    let state = game.state.lock().await.clone();
+
+    Ok(Json(LiveGameResponse { game_id, state }))
+}
+
+/// The websocket for a live game. It sends the state as soon as it opens,
+/// then again after every change anyone makes. Changes are LiveCommands.
+#[axum::debug_handler]
+pub async fn live_game_socket(
+    Extension(pool): Extension<PgPool>,
+    Extension(live_games): Extension<LiveGames>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>,
+    ws: WebSocketUpgrade
+) -> Result<impl IntoResponse, ApiError> {
+    let can_write = has_permission(&auth_session, Permission::Write).await?;
+    let user = signed_in_user(auth_session)?;
+    let game = live_games.get(&pool, game_id, user.id()).await?;
+
+    Ok(ws.on_upgrade(move |socket| watch_live_game(socket, pool, game, can_write)))
+}
+
+fn message_text(message: &LiveMessage) -> Message {
+    Message::Text(serde_json::to_string(message).unwrap_or_default())
+}
+
+async fn watch_live_game(socket: WebSocket, pool: PgPool, game: Arc<LiveGame>, can_write: bool) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut updates = game.updates.subscribe();
+
+    let state = game.state.lock().await.clone();
+    if sender.send(message_text(&LiveMessage::State { game_id: game.id, state })).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            update = updates.recv() => {
+                let message = match update {
+                    Ok(message) => message,
+                    // Missed some updates, but only the latest state matters
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        LiveMessage::State { game_id: game.id, state: game.state.lock().await.clone() }
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break
+                };
+
+                let done = matches!(message, LiveMessage::Finalized { .. } | LiveMessage::Discarded { .. });
+                if sender.send(message_text(&message)).await.is_err() || done {
+                    break;
+                }
+            },
+            message = receiver.next() => {
+                match message {
+                    Some(Ok(Message::Text(_))) if !can_write => {
+                        let message = String::from("Your account is read-only, you can only watch");
+                        if sender.send(message_text(&LiveMessage::Error { message })).await.is_err() {
+                            break;
+                        }
+                    },
+                    Some(Ok(Message::Text(text))) => {
+                        if let Err(message) = game.handle(&pool, &text).await {
+                            if sender.send(message_text(&LiveMessage::Error { message })).await.is_err() {
+                                break;
+                            }
+                        }
+                    },
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+/// Finishes a live game. The state has to be a valid GameData that passes
+/// the same checks as POST /api/games, then it's written to the *Entries
+/// tables and the game stops being a draft.
+#[axum::debug_handler]
+pub async fn finalize_live_game(
+    Extension(pool): Extension<PgPool>,
+    Extension(live_games): Extension<LiveGames>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let game = live_games.get(&pool, game_id, user.id()).await?;
+
+    // Held until the end so no one changes it while it's being finished
+    let state = game.state.lock().await;
+    let mut mp_data: GameData = serde_json::from_value(state.clone()).map_err(|err| {
+        println!("Invalid live game: {err}");
+        ApiError::BadRequest(err.to_string())
+    })?;
+    check_game(&mp_data)?;
+
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query("
+            UPDATE Games SET date = $1, turns = $2, board = $3, game = $4, draft = false, live_state = NULL
+            WHERE id = $5 AND group_id = $6 AND draft")
+        .bind(mp_data.date)
+        .bind(mp_data.turns)
+        .bind(&mp_data.board)
+        .bind(mp_data.player_data.game_name())
+        .bind(game_id)
+        .bind(game.group_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(String::from("There's no game being played with that id")));
+    }
+
+    let date: DateTime<Utc> = mp_data.date;
+    resolve_players(&mut tx, game.group_id, &mut mp_data.player_data).await?;
+    insert_player_data(&mut tx, game_id, mp_data.player_data).await?;
+    recompute_from(&mut tx, game.group_id, date).await?;
+
+    tx.commit().await?;
+
+    live_games.remove(game_id);
+    let _ = game.updates.send(LiveMessage::Finalized { game_id });
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Finished game successfully"),
+            success: true
+        }
+    ))
+}
+
+/// Throws a live game away. Like deleting a finished game, only whoever
+/// started it or an owner of its group can.
+#[axum::debug_handler]
+pub async fn discard_live_game(
+    Extension(pool): Extension<PgPool>,
+    Extension(live_games): Extension<LiveGames>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+    owned_game(&mut tx, game_id, user.id()).await?;
+
+    let result = sqlx::query("DELETE FROM Games WHERE id = $1 AND draft")
+        .bind(game_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(String::from("There's no game being played with that id")));
+    }
+
+    tx.commit().await?;
+
+    if let Some(game) = live_games.remove(game_id) {
+        let _ = game.updates.send(LiveMessage::Discarded { game_id });
+    }
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Discarded game successfully"),
+            success: true
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live_game(id: i32, idle_for: Duration) -> Arc<LiveGame> {
+        let (updates, _) = broadcast::channel(1);
+        Arc::new(LiveGame {
+            id,
+            group_id: 1,
+            fields: vec![],
+            state: tokio::sync::Mutex::new(json!({})),
+            updates,
+            last_used: Mutex::new(Instant::now() - idle_for)
+        })
+    }
+
+    fn game_ids(live_games: &LiveGames) -> Vec<i32> {
+        let mut ids: Vec<i32> = live_games.0.lock().unwrap().keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn drops_games_idle_for_too_long() {
+        let live_games = LiveGames::default();
+        live_games.0.lock().unwrap().insert(1, live_game(1, Duration::from_secs(10)));
+        live_games.0.lock().unwrap().insert(2, live_game(2, Duration::from_secs(120)));
+
+        live_games.drop_idle(Duration::from_secs(60));
+        assert_eq!(game_ids(&live_games), vec![1]);
+    }
+
+    #[test]
+    fn keeps_idle_games_someone_is_watching() {
+        let live_games = LiveGames::default();
+        let game = live_game(1, Duration::from_secs(120));
+        let _watching = game.updates.subscribe();
+        live_games.0.lock().unwrap().insert(1, game);
+
+        live_games.drop_idle(Duration::from_secs(60));
+        assert_eq!(game_ids(&live_games), vec![1]);
+    }
+
+    #[test]
+    fn using_a_game_keeps_it() {
+        let live_games = LiveGames::default();
+        let game = live_game(1, Duration::from_secs(120));
+        game.touch();
+        live_games.0.lock().unwrap().insert(1, game);
+
+        live_games.drop_idle(Duration::from_secs(60));
+        assert_eq!(game_ids(&live_games), vec![1]);
+    }
+}
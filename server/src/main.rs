@@ -1,25 +1,46 @@
-use tokio;
 use clap::Parser;
 use std::env;
 use sqlx::postgres::PgPoolOptions;
 use axum::{
     Extension,
-    routing::{get, post, get_service},
+    routing::{delete, get, post, put, get_service},
     Router,
-    http::StatusCode
+    extract::DefaultBodyLimit,
+    http::StatusCode,
+    middleware
 };
 use axum_login::{
     login_required,
-    tower_sessions::{Expiry, MemoryStore, SessionManagerLayer},
+    tower_sessions::{cookie::time::Duration, Expiry, SessionManagerLayer},
     AuthManagerLayerBuilder};
 use tower::ServiceBuilder;
 use tower_http::{cors::CorsLayer, services::ServeDir, services::ServeFile};
 use crate::routes::Backend;
+use crate::sessions::PgSessionStore;
+use crate::live::LiveGames;
 
 pub mod routes;
 pub mod requests;
 pub mod responses;
 pub mod listfields;
+pub mod boards;
+pub mod errors;
+pub mod stats;
+pub mod players;
+pub mod sessions;
+pub mod openapi;
+pub mod entries;
+pub mod export;
+pub mod import;
+pub mod backup;
+pub mod ratings;
+pub mod spaces;
+pub mod live;
+pub mod timeline;
+pub mod minigames;
+pub mod groups;
+pub mod permissions;
+pub mod admin;
 
 
 #[derive(Parser, Debug)]
@@ -38,7 +59,23 @@ struct CliOptions {
 
     /// set whether this is a dev server
     #[clap(long = "dev", default_value = "false")]
-    dev: bool
+    dev: bool,
+
+    /// set how many days a login lasts, however active it is, up to 3650
+    #[clap(long = "session-lifetime-days", default_value = "30", value_parser = clap::value_parser!(i64).range(1..=3650))]
+    session_lifetime_days: i64,
+
+    /// set how many days a login lasts without being used, up to 3650
+    #[clap(long = "session-inactivity-days", default_value = "7", value_parser = clap::value_parser!(i64).range(1..=3650))]
+    session_inactivity_days: i64,
+
+    /// set how many minutes between deleting expired sessions, up to a week
+    #[clap(long = "session-cleanup-minutes", default_value = "60", value_parser = clap::value_parser!(u64).range(1..=10080))]
+    session_cleanup_minutes: u64,
+
+    /// set how many minutes a live game nobody is watching stays in memory, up to a week
+    #[clap(long = "live-game-idle-minutes", default_value = "60", value_parser = clap::value_parser!(u64).range(1..=10080))]
+    live_game_idle_minutes: u64
 }
 
 #[tokio::main]
@@ -63,10 +100,25 @@ async fn main() -> Result<(), sqlx::Error> {
         .run(&pool)
         .await?;
 
+    ratings::backfill(&pool).await?;
+
     // Session layer.
-    let session_store = MemoryStore::default();
+    let session_store = PgSessionStore::new(pool.clone(), Duration::days(opts.session_lifetime_days));
+    tokio::task::spawn(
+        session_store
+            .clone()
+            .continuously_delete_expired(std::time::Duration::from_secs(opts.session_cleanup_minutes * 60))
+    );
     let session_layer = SessionManagerLayer::new(session_store)
-        .with_expiry(Expiry::OnSessionEnd);
+        .with_expiry(Expiry::OnInactivity(Duration::days(opts.session_inactivity_days)));
+
+    // Live games are kept in memory while they're being played
+    let live_games = LiveGames::default();
+    tokio::task::spawn(
+        live_games
+            .clone()
+            .continuously_drop_idle(std::time::Duration::from_secs(opts.live_game_idle_minutes * 60))
+    );
 
     // Auth service.
     let backend = Backend::new(pool.clone());
@@ -77,6 +129,32 @@ async fn main() -> Result<(), sqlx::Error> {
         |_| async move { (StatusCode::INTERNAL_SERVER_ERROR, "internal server error") },
     );
 
+    // Everything that changes a group or what's in it, which read-only users can't do
+    let write_routes = Router::new()
+        // Backups of a whole account can be a lot bigger than axum's 2MB default
+        .route("/api/me/restore", post(backup::restore).layer(DefaultBodyLimit::max(64 * 1024 * 1024)))
+        .route("/api/groups", post(groups::create_group))
+        .route("/api/groups/:id", put(groups::update_group).delete(groups::delete_group))
+        .route("/api/groups/:id/members/:user_id", put(groups::update_member).delete(groups::remove_member))
+        .route("/api/groups/:id/invitations", post(groups::invite))
+        .route("/api/games", post(routes::games))
+        .route("/api/games/:id", put(routes::update_game).delete(routes::delete_game))
+        .route("/api/games/:id/turns", put(timeline::update_game_turns))
+        .route("/api/games/:id/minigames", put(minigames::update_game_minigame_results))
+        .route("/api/players", post(players::create_player))
+        .route("/api/players/:id", put(players::update_player).delete(players::delete_player))
+        .route("/api/live", post(live::start_live_game))
+        .route("/api/live/:id", delete(live::discard_live_game))
+        .route("/api/live/:id/finalize", post(live::finalize_live_game))
+        .route("/api/import", post(import::import))
+        .route_layer(middleware::from_fn(permissions::require_write));
+
+    // Managing other users
+    let admin_routes = Router::new()
+        .route("/api/admin/users", get(admin::list_users))
+        .route("/api/admin/users/:id", put(admin::update_user).delete(admin::delete_user))
+        .route_layer(middleware::from_fn(permissions::require_admin));
+
     let mut app = Router::new()
         .route(
             "/protected",
@@ -85,13 +163,45 @@ async fn main() -> Result<(), sqlx::Error> {
         .route_layer(login_required!(Backend, login_url = "/login"))
         .route("/api/login", post(routes::login))
         .route("/api/signup", post(routes::signup))
-        .route("/api/games", post(routes::games))
+        .route("/api/logout", post(routes::logout))
+        .route("/api/me", get(routes::me))
+        .route("/api/me/password", post(routes::change_password))
+        .route("/api/me/backup", get(backup::backup))
+        .route("/api/groups", get(groups::list_groups))
+        .route("/api/groups/:id", get(groups::group))
+        .route("/api/groups/:id/invitations", get(groups::group_invitations))
+        .route("/api/invitations", get(groups::my_invitations))
+        .route("/api/invitations/:id", delete(groups::delete_invitation))
+        .route("/api/invitations/:id/accept", post(groups::accept_invitation))
+        .route("/api/games", get(routes::list_games))
+        .route("/api/games/:id", get(routes::game))
+        .route("/api/games/:id/turns", get(timeline::game_turns))
+        .route("/api/games/:id/minigames", get(minigames::game_minigame_results))
+        .route("/api/players", get(players::list_players))
+        .route("/api/players/:id", get(players::player))
+        .route("/api/stats/leaderboard", get(stats::leaderboard))
+        .route("/api/stats/characters", get(stats::character_stats))
+        .route("/api/stats/h2h", get(stats::head_to_head))
+        .route("/api/stats/spaces", get(spaces::space_stats))
+        .route("/api/stats/minigames", get(minigames::minigame_stats))
+        .route("/api/minigames", get(minigames::minigame_catalog))
+        .route("/api/ratings", get(ratings::ratings))
+        .route("/api/ratings/:player_id", get(ratings::rating_history))
+        .route("/api/live", get(live::list_live_games))
+        .route("/api/live/:id", get(live::live_game))
+        // Read-only users can watch, but what they send is turned away
+        .route("/api/live/:id/ws", get(live::live_game_socket))
+        .route("/api/export.csv", get(export::export_csv))
+        .route("/api/export.zip", get(export::export_zip))
         .route("/api/input/schema", get(routes::input_schema))
-        .route("/api/characters", get(routes::characters))
+        .route("/api/openapi.json", get(openapi::openapi))
+        .merge(write_routes)
+        .merge(admin_routes)
         .layer(auth_layer)
         .layer(
             ServiceBuilder::new()
                 .layer(Extension(pool))
+                .layer(Extension(live_games))
                 .layer(CorsLayer::permissive())
         );
 
@@ -0,0 +1,647 @@
+use std::collections::{HashMap, HashSet};
+use sqlx::postgres::{PgConnection, PgPool};
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::{Path, Query},
+    response::IntoResponse,
+    Json
+};
+use crate::requests::{GroupQuery, MinigameCatalogQuery, MinigameResultData, MinigameStatsQuery, MinigameType, PlayerRef};
+use crate::responses::{MinigameCatalogResponse, MinigamePlayerStats, MinigameResultResponse, MinigameStats, PlayerResponse};
+use crate::errors::ApiError;
+use crate::routes::{AuthSession, owned_game, signed_in_user};
+use crate::groups::member_group;
+use crate::stats::scorings_for;
+
+/// The minigames of one title, by the names the game shows for them. These
+/// are what gets stored in MinigameResults.minigame, so renaming one needs a
+/// migration.
+pub struct Catalog {
+    /// The name of the game, as in the Games.game column
+    pub game: &'static str,
+    pub minigames: &'static [&'static str]
+}
+
+// These aren't every minigame of every title yet, add them as they come up.
+// Mario Party 10, Mario Party DS, Island Tour, Star Rush, Super Mario Party
+// and Jamboree aren't listed at all, so their games take any minigame name
+// until they are.
+pub const MINIGAMES: [Catalog; 11] = [
+    Catalog { game: "Mario Party", minigames: &[
+        "Balloon Burst", "Bash 'n' Cash", "Bobsled Run", "Bombs Away", "Bombsketball", "Bowl Over",
+        "Box Mountain Mayhem", "Bumper Balls", "Buried Treasure", "Cast Aways", "Coin Block Bash",
+        "Coin Block Blitz", "Crane Game", "Crazy Cutter", "Deep Sea Divers", "Desert Dash", "Face Lift",
+        "Grab Bag", "Ground Pound", "Hammer Drop", "Handcar Havoc", "Hexagon Heat", "Hot Bob-omb",
+        "Hot Rope Jump", "Key-pa-Way", "Mario Bandstand", "Mushroom Mix-Up", "Musical Mushroom",
+        "Paddle Battle", "Piranha's Pursuit", "Pipe Maze", "Platform Peril", "Shy Guy Says",
+        "Skateboard Scamper", "Slot Car Derby", "Teetering Towers", "Tightrope Treachery", "Tipsy Tourney",
+        "Treasure Divers", "Tug o' War", "Whack-a-Plant"
+    ] },
+    Catalog { game: "Mario Party 2", minigames: &[
+        "Archer-ival", "Bob-omb Barrage", "Bowl Over", "Bowser's Big Blast", "Bumper Balloon Cars",
+        "Bumper Balls", "Cake Factory", "Crane Game", "Crazy Cutters", "Day at the Races", "Dizzy Dancing",
+        "Face Lift", "Filet Relay", "Grab Bag", "Handcar Havoc", "Hexagon Heat", "Honeycomb Havoc",
+        "Hot Bob-omb", "Hot Rope Jump", "Lava Tile Isle", "Lights Out", "Look Away", "Mecha-Marathon",
+        "Move to the Music", "Quicksand Cache", "Rainbow Run", "Rakin' 'em In", "Shock, Drop or Roll",
+        "Shy Guy Says", "Skateboard Scamper", "Slot Car Derby", "Sneak 'n' Snore", "Speed Hockey",
+        "Tile Driver", "Toad in the Box"
+    ] },
+    Catalog { game: "Mario Party 3", minigames: &[
+        "Aces High", "All Fired Up", "Awful Tower", "Baby Bowser Broadside", "Bobbing Bow-loons",
+        "Boulder Ball", "Bounce 'n' Trounce", "Bowser Toss", "Cheep Cheep Chase", "Chip Shot Challenge",
+        "Coconut Conk", "Cosmic Coaster", "Crazy Cogs", "Curtain Call", "Dorrie Dip", "Eatsa Pizza",
+        "End of the Line", "Etch 'n' Catch", "Eye Sore", "Frigid Bridges", "Hand, Line and Sinker",
+        "Hey, Batter, Batter!", "Hide and Sneak", "Hyper Hydrants", "Ice Rink Risk", "Log Jam",
+        "M.P.I.Q.", "Mario's Puzzle Party", "Merry-Go-Chomp", "Messy Memory", "Motor Rooter",
+        "Parasol Plummet", "Picking Panic", "Picture Imperfect", "Pipe Cleaners", "Popgun Pick-Off",
+        "Puddle Paddle", "Pump, Pump and Away", "Ridiculous Relay", "River Raiders", "Rockin' Raceway",
+        "Silly Screws", "Slap Down", "Slot Synch", "Snowball Summit", "Spotlight Swim", "Stacked Deck",
+        "Storm Chasers", "Swing 'n' Swipe", "The Beat Goes On", "Three Door Monty", "Thwomp Pull",
+        "Tidal Toss", "Toadstool Titan", "Treadmill Grill", "Vine With Me", "Water Whirled"
+    ] },
+    Catalog { game: "Mario Party 4", minigames: &[
+        "Avalanche!", "Barrel Baron", "Beach Volley Folley", "Bob-omb Breakers", "Booksquirm",
+        "Bowser Bop", "Bowser Wrestling", "Chain Chomp Fever", "Cheep Cheep Sweep", "Darts of Doom",
+        "Dodge Bomb", "Domination", "Dungeon Duos", "Fish n' Drips", "Goomba Stomp", "Hide and Go BOOM!",
+        "Jigsaw Jitters", "Kareening Koopa", "Long Claw of the Law", "Manic Mallets", "Mario Medley",
+        "Mario Speedwagons", "Mr. Blizzard's Brigade", "Mushroom Medic", "Order Up", "Panel Panic",
+        "Paratrooper Flight", "Paths of Peril", "Photo Finish", "Revers-a-Bomb", "Rumble Fishing",
+        "Slime Time", "Take a Breather", "Three Throw", "Toad's Quick Draw", "Trace Race", "Tree Stomp"
+    ] },
+    Catalog { game: "Mario Party 5", minigames: &[
+        "Banking Coins", "Beam Team", "Bill Blasters", "Blown Away", "Chimp Chase", "Chomp Romp",
+        "Clock Stoppers", "Coney Island", "Curvy Curbs", "Defuse or Lose", "Dinger Derby", "Fight Cards",
+        "Fish Upon a Star", "Flatiator", "Frozen Frenzy", "Ground Pound Down", "Heat Stroke", "Hope Floats",
+        "Hotel Goomba", "Hydrostars", "ID UFO", "Later Skater", "Leaf Leap", "Lucky Lineup", "Mario Mechs",
+        "Mazed & Confused", "Night Light Fright", "Pop-Star Piranhas", "Pound Peril", "Pushy Penguins",
+        "Quilt for Speed", "Random Ride", "Revolving Fire", "Rumble Fumble", "Shock Absorbers",
+        "Squared Away", "Triple Jump", "Tube It or Lose It", "Tug-o-Dorrie", "Twist 'n' Out"
+    ] },
+    Catalog { game: "Mario Party 6", minigames: &[
+        "Ball Dozers", "Body Builder", "Cannonball Fun", "Cash Flow", "Cashapult", "Catch You Letter",
+        "Circuit Maximus", "Cog Jog", "Control Schtick", "Crate and Peril", "Daft Rafts", "Dark 'n Crispy",
+        "Freeze Frame", "Granite Getaway", "Lift Leapers", "Mass A-peel", "Memory Lane", "Mole-it!",
+        "Money Belt", "Mowtown", "Note to Self", "Odd Card Out", "Pitifall", "Pokey Punch-out",
+        "Rocky Road", "Same is Lame", "Sink or Swim", "Smashdance", "Snow Brawl", "Snow Whirled",
+        "Stamp By Me", "Strawberry Shortfuse", "Surge and Purge", "Trap Ease Artist", "Treasure Trawlers",
+        "Tricky Tires", "Verbal Assault", "What Goes Up...", "Word Herd"
+    ] },
+    Catalog { game: "Mario Party 7", minigames: &[
+        "Balloon Busters", "Bridge Work", "Bubble Brawl", "Bumper Crops", "Camp Ukiki", "Catchy Tunes",
+        "Clock Watchers", "Cointagious", "Dart Attack", "Fun Run", "Funstacle Course!", "Ghost in the Hall",
+        "Grin and Bear It", "Helipopper", "Hop-O-Matic 4000", "Ice Moves", "Jump, Man", "La Bomba",
+        "Light Breeze", "Mad Props", "Mathletes", "Picture This", "Pogo-a-Go-Go", "Pokey Pummel",
+        "Royal Rumpus", "Snow Ride", "Sphere Factor", "Spin Doctor", "Stick and Spin", "Target Tag",
+        "Tile and Error", "Track & Yield", "Vine Country", "Wheel of Woe", "Wingin' It"
+    ] },
+    Catalog { game: "Mario Party 8", minigames: &[
+        "Aim of the Game", "Attention Grabber", "Balancing Act", "Blazing Lassos", "Breakneck Building",
+        "Bull Riding", "Bumper Balloons", "Cardiators", "Chump Rope", "Cosmic Slalom", "Crops 'n' Robbers",
+        "Flip the Chimp", "Frozen Assets", "Glacial Meltdown", "Grabbin' Gold", "Ion the Prize",
+        "Kartastrophe", "King of the Thrill", "Lava Lobbers", "Lava or Leave 'Em", "Lean, Mean Ice Machine",
+        "Lob to Rob", "Loco Motives", "Moped Mayhem", "Paint Misbehavin'", "Picture Perfect",
+        "Pour to Score", "Pumper Cars", "Punch-a-Bunch", "Rowed to Victory", "Saucer Swarm",
+        "Shake It Up", "Sick and Twisted", "Snipe for the Picking", "Specter Inspector", "Sugar Rush",
+        "Surf's Way Up", "Trial by Tile", "Wing and a Scare", "Winner or Dinner", "You're the Bob-omb"
+    ] },
+    Catalog { game: "Mario Party 9", minigames: &[
+        "Bomb Barge", "Bowser Pop", "Don't Look", "Fungi Frenzy", "Goomba Bowling", "Logger Heads",
+        "Peak Precision", "Pix Fix", "Plunder Ground", "Skyjinks", "Smash Compactor", "Speeding Bullets",
+        "Tackle Takedown"
+    ] },
+    Catalog { game: "Mario Party: The Top 100", minigames: &[
+        "Bowser's Big Blast", "Chimp Chase", "Coconut Conk", "Face Lift", "Hexagon Heat", "Hide and Sneak",
+        "Leaf Leap", "Manic Mallets", "Mario Medley", "Mushroom Medic", "Paths of Peril", "Pushy Penguins",
+        "Rumble Fishing", "Shy Guy Says", "Trace Race"
+    ] },
+    Catalog { game: "Mario Party Superstars", minigames: &[
+        "Beam Team", "Bowl Over", "Bowser's Big Blast", "Bubble Brawl", "Bumper Balls", "Catch You Letter",
+        "Catchy Tunes", "Chimp Chase", "Circuit Maximus", "Coconut Conk", "Crane Game", "Crazy Cogs",
+        "Dinger Derby", "Dizzy Dancing", "Dungeon Duos", "Eatsa Pizza", "Face Lift", "Freeze Frame",
+        "Fun Run", "Granite Getaway", "Ground Pound Down", "Hand, Line and Sinker", "Handcar Havoc",
+        "Hexagon Heat", "Hide and Sneak", "Honeycomb Havoc", "Hot Rope Jump", "Hotel Goomba",
+        "Later Skater", "Leaf Leap", "Long Claw of the Law", "Manic Mallets", "Mario Medley",
+        "Mazed & Confused", "Mr. Blizzard's Brigade", "Mushroom Medic", "Mushroom Mix-Up",
+        "Night Light Fright", "Odd Card Out", "Platform Peril", "Pushy Penguins", "Revers-a-Bomb",
+        "Shy Guy Says", "Skateboard Scamper", "Slot Car Derby", "Smashdance", "Sneak 'n' Snore",
+        "Snow Whirled", "Spotlight Swim", "Tile Driver", "Toad in the Box", "Trace Race", "Track & Yield",
+        "Tube It or Lose It", "Tug o' War", "What Goes Up..."
+    ] }
+];
+
+/// The minigames of `game` or every title, for ?game=
+fn catalogs_for(game: Option<&str>) -> Result<Vec<&'static Catalog>, ApiError> {
+    let scorings = scorings_for(game)?;
+    let catalogs = MINIGAMES.iter()
+        .filter(|catalog| scorings.iter().any(|scoring| scoring.game == catalog.game))
+        .collect();
+
+    Ok(catalogs)
+}
+
+/// Looks `minigame` up in `game`'s catalog, ignoring case, and gives back
+/// the name it's stored under
+pub fn find_minigame(game: &str, minigame: &str) -> Option<&'static str> {
+    MINIGAMES.iter()
+        .find(|catalog| catalog.game == game)?
+        .minigames.iter()
+        .find(|name| name.eq_ignore_ascii_case(minigame.trim()))
+        .copied()
+}
+
+/// The minigames we know about, for every title or just ?game=
+#[axum::debug_handler]
+pub async fn minigame_catalog(
+    Query(params): Query<MinigameCatalogQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let catalogs: Vec<MinigameCatalogResponse> = catalogs_for(params.game.as_deref())?.into_iter()
+        .map(|catalog| MinigameCatalogResponse {
+            game: catalog.game.to_string(),
+            minigames: catalog.minigames.iter().map(|minigame| minigame.to_string()).collect()
+        })
+        .collect();
+
+    Ok(Json(catalogs))
+}
+
+// A finished game and who was in it
+struct MinigameGame {
+    game: String,
+    turns: i32,
+    players: Vec<PlayerResponse>
+}
+
+async fn minigame_game(conn: &mut PgConnection, game_id: i32, user_id: i32) -> Result<MinigameGame, ApiError> {
+    let row: Option<(Option<String>, i32, bool)> = sqlx::query_as("
+            SELECT game, turns, draft FROM Games
+            WHERE id = $1 AND group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $2)")
+        .bind(game_id)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let (game, turns) = match row {
+        Some((_, _, true)) => {
+            return Err(ApiError::BadRequest(String::from("That game is still being played, finish it from /api/live first")));
+        },
+        Some((Some(game), turns, false)) => (game, turns),
+        _ => return Err(ApiError::NotFound(String::from("Game not found")))
+    };
+
+    let scoring = scorings_for(Some(&game))?[0];
+    let players: Vec<PlayerResponse> = sqlx::query_as(&format!("
+            SELECT Players.id, Players.name FROM {} e
+                JOIN Players ON Players.id = e.player_id
+            WHERE e.game_id = $1
+            ORDER BY e.id",
+            scoring.table))
+        .bind(game_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(MinigameGame { game, turns, players })
+}
+
+fn find_player(game: &MinigameGame, player: &PlayerRef) -> Result<i32, String> {
+    let found = match player {
+        PlayerRef::Id(id) => game.players.iter().find(|p| p.id == *id),
+        PlayerRef::Name(name) => game.players.iter().find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+    };
+
+    found.map(|p| p.id).ok_or_else(|| format!("{player} isn't in this game"))
+}
+
+// A result that's been checked against its game, ready to insert
+struct CheckedResult {
+    turn: Option<i32>,
+    minigame: String,
+    minigame_type: &'static str,
+    // Who played it, and whether they won
+    players: Vec<(i32, bool)>,
+    // The player on their own, for 1v3
+    solo: Option<i32>
+}
+
+fn check_result(game: &MinigameGame, result: &MinigameResultData) -> Result<CheckedResult, String> {
+    if result.turn.is_some_and(|turn| turn < 1 || turn > game.turns) {
+        return Err(format!("the game only had {} turns", game.turns));
+    }
+
+    // Titles we don't have a list for take whatever the minigame is called
+    let minigame = if MINIGAMES.iter().any(|catalog| catalog.game == game.game) {
+        find_minigame(&game.game, &result.minigame)
+            .ok_or_else(|| format!("\"{}\" isn't one of the minigames of {}, see /api/minigames", result.minigame.trim(), game.game))?
+            .to_string()
+    }
+    else {
+        result.minigame.trim().to_string()
+    };
+    if minigame.is_empty() {
+        return Err(String::from("the minigame needs a name"));
+    }
+
+    // Everyone in the game plays, unless it says otherwise
+    let players: Vec<i32> = match &result.players {
+        Some(players) => players.iter().map(|player| find_player(game, player)).collect::<Result<_, _>>()?,
+        None if result.minigame_type == MinigameType::Duel => {
+            return Err(String::from("say which two players were in the duel with players"));
+        },
+        None => game.players.iter().map(|player| player.id).collect()
+    };
+    if players.is_empty() {
+        return Err(String::from("someone has to play it, leave players out if everyone did"));
+    }
+    if players.iter().collect::<HashSet<_>>().len() != players.len() {
+        return Err(String::from("a player is in it more than once"));
+    }
+    match result.minigame_type {
+        MinigameType::Duel if players.len() != 2 => return Err(String::from("a duel is between two players")),
+        MinigameType::OneVsThree | MinigameType::TwoVsTwo if players.len() != 4 => {
+            return Err(format!("a {} minigame is between four players", result.minigame_type.as_str()));
+        },
+        _ => {}
+    }
+
+    let winners: Vec<i32> = result.winners.iter().map(|winner| find_player(game, winner)).collect::<Result<_, _>>()?;
+    if let Some(winner) = winners.iter().find(|winner| !players.contains(winner)) {
+        let name = game.players.iter().find(|player| player.id == *winner).map(|player| player.name.as_str()).unwrap_or_default();
+        return Err(format!("{name} won but didn't play"));
+    }
+    if winners.iter().collect::<HashSet<_>>().len() != winners.len() {
+        return Err(String::from("a player won it more than once"));
+    }
+
+    let solo = match (&result.minigame_type, &result.solo) {
+        (MinigameType::OneVsThree, Some(solo)) => {
+            let solo = find_player(game, solo)?;
+            if !players.contains(&solo) {
+                return Err(String::from("the player on their own has to be one of the players"));
+            }
+            Some(solo)
+        },
+        (MinigameType::OneVsThree, None) => return Err(String::from("say which player was on their own with solo")),
+        (_, Some(_)) => return Err(String::from("only a 1v3 minigame has a player on their own")),
+        (_, None) => None
+    };
+
+    // The winners are a whole team, or nobody if it was a tie
+    match result.minigame_type {
+        MinigameType::OneVsThree => {
+            // Either the player on their own won, or the three against them did
+            let solo_won = solo.is_some_and(|solo| winners.contains(&solo));
+            if !(winners.is_empty() || (winners.len() == 1 && solo_won) || (winners.len() == 3 && !solo_won)) {
+                return Err(String::from("a 1v3 minigame is won by the player on their own or the team of three"));
+            }
+        },
+        MinigameType::TwoVsTwo if ![0, 2].contains(&winners.len()) => {
+            return Err(String::from("a 2v2 minigame is won by a team of two"));
+        },
+        _ => {}
+    }
+
+    Ok(CheckedResult {
+        turn: result.turn,
+        minigame,
+        minigame_type: result.minigame_type.as_str(),
+        players: players.into_iter().map(|player| (player, winners.contains(&player))).collect(),
+        solo
+    })
+}
+
+#[derive(sqlx::FromRow)]
+struct ResultRow {
+    id: i32,
+    turn: Option<i32>,
+    minigame: String,
+    minigame_type: String,
+    player_id: i32,
+    player_name: String,
+    won: bool,
+    solo: bool
+}
+
+// Adds one minigame to a game. `players` is who played it and whether they
+// won, `solo` the one on their own in a 1v3.
+async fn insert_minigame(
+    conn: &mut PgConnection,
+    game_id: i32,
+    turn: Option<i32>,
+    minigame: &str,
+    minigame_type: &str,
+    players: Vec<(i32, bool)>,
+    solo: Option<i32>
+) -> Result<(), sqlx::Error> {
+    let result_id: i32 = sqlx::query_scalar("
+            INSERT INTO MinigameResults (game_id, turn, minigame, minigame_type) VALUES ($1, $2, $3, $4)
+            RETURNING id")
+        .bind(game_id)
+        .bind(turn)
+        .bind(minigame)
+        .bind(minigame_type)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let (player_ids, won): (Vec<i32>, Vec<bool>) = players.into_iter().unzip();
+    let is_solo: Vec<bool> = player_ids.iter().map(|player_id| solo == Some(*player_id)).collect();
+    sqlx::query("
+            INSERT INTO MinigamePlayers (result_id, player_id, won, solo)
+            SELECT $1, * FROM UNNEST($2::int[], $3::bool[], $4::bool[])")
+        .bind(result_id)
+        .bind(player_ids)
+        .bind(won)
+        .bind(is_solo)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn game_minigames(conn: &mut PgConnection, game_id: i32) -> Result<Vec<MinigameResultResponse>, ApiError> {
+    let rows: Vec<ResultRow> = sqlx::query_as("
+            SELECT r.id, r.turn, r.minigame, r.minigame_type, p.player_id, Players.name AS player_name, p.won, p.solo
+            FROM MinigameResults r
+                JOIN MinigamePlayers p ON p.result_id = r.id
+                JOIN Players ON Players.id = p.player_id
+            WHERE r.game_id = $1
+            ORDER BY r.turn NULLS LAST, r.id, p.player_id")
+        .bind(game_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let results = rows.chunk_by(|a, b| a.id == b.id).map(|rows| {
+        let player = |row: &ResultRow| PlayerResponse { id: row.player_id, name: row.player_name.clone() };
+        MinigameResultResponse {
+            turn: rows[0].turn,
+            minigame: rows[0].minigame.clone(),
+            minigame_type: rows[0].minigame_type.clone(),
+            players: rows.iter().map(player).collect(),
+            winners: rows.iter().filter(|row| row.won).map(player).collect(),
+            solo: rows.iter().find(|row| row.solo).map(player)
+        }
+    }).collect();
+
+    Ok(results)
+}
+
+/// The minigames played in a finished game
+#[axum::debug_handler]
+pub async fn game_minigame_results(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut conn = pool.acquire().await?;
+    minigame_game(&mut conn, game_id, user.id()).await?;
+
+    Ok(Json(game_minigames(&mut conn, game_id).await?))
+}
+
+/// Replaces the minigames played in a finished game
+#[axum::debug_handler]
+pub async fn update_game_minigame_results(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>,
+    Json(results): Json<Vec<MinigameResultData>>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+    owned_game(&mut tx, game_id, user.id()).await?;
+    replace_minigames(&mut tx, game_id, user.id(), &results).await?;
+
+    let results = game_minigames(&mut tx, game_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(results))
+}
+
+/// Checks each result against the game and puts them in place of its
+/// minigames. Restoring a backup checks its minigames with this too.
+pub async fn replace_minigames(conn: &mut PgConnection, game_id: i32, user_id: i32, results: &[MinigameResultData]) -> Result<(), ApiError> {
+    let game = minigame_game(&mut *conn, game_id, user_id).await?;
+
+    let mut checked = Vec::new();
+    for (i, result) in results.iter().enumerate() {
+        let result = check_result(&game, result)
+            .map_err(|message| ApiError::BadRequest(format!("Minigame {}: {}", i + 1, message)))?;
+        checked.push(result);
+    }
+
+    sqlx::query("DELETE FROM MinigameResults WHERE game_id = $1")
+        .bind(game_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for result in checked {
+        insert_minigame(&mut *conn, game_id, result.turn, &result.minigame, result.minigame_type, result.players, result.solo).await?;
+    }
+
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct StatsRow {
+    game: String,
+    minigame: String,
+    player_id: i32,
+    player_name: String,
+    played: i64,
+    wins: i64
+}
+
+/// How often each player wins each minigame they've played, optionally for
+/// one title and one minigame. Most played minigames come first, and in each
+/// one the best win rate does.
+#[axum::debug_handler]
+pub async fn minigame_stats(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<MinigameStatsQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+    scorings_for(params.game.as_deref())?;
+    let minigame = params.minigame.as_deref().map(str::trim).filter(|minigame| !minigame.is_empty());
+
+    let times_played: HashMap<(String, String), i64> = sqlx::query_as("
+            SELECT Games.game, r.minigame, COUNT(*)
+            FROM MinigameResults r
+                JOIN Games ON Games.id = r.game_id
+            WHERE Games.group_id = $1 AND NOT Games.draft
+                AND ($2::text IS NULL OR Games.game = $2)
+                AND ($3::text IS NULL OR lower(r.minigame) = lower($3))
+            GROUP BY Games.game, r.minigame")
+        .bind(group_id)
+        .bind(&params.game)
+        .bind(minigame)
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(|(game, minigame, count)| ((game, minigame), count))
+        .collect();
+
+    let rows: Vec<StatsRow> = sqlx::query_as("
+            SELECT
+                Games.game,
+                r.minigame,
+                p.player_id,
+                Players.name AS player_name,
+                COUNT(*) AS played,
+                COUNT(*) FILTER (WHERE p.won) AS wins
+            FROM MinigameResults r
+                JOIN Games ON Games.id = r.game_id
+                JOIN MinigamePlayers p ON p.result_id = r.id
+                JOIN Players ON Players.id = p.player_id
+            WHERE Games.group_id = $1 AND NOT Games.draft
+                AND ($2::text IS NULL OR Games.game = $2)
+                AND ($3::text IS NULL OR lower(r.minigame) = lower($3))
+            GROUP BY Games.game, r.minigame, p.player_id, Players.name
+            ORDER BY Games.game, r.minigame")
+        .bind(group_id)
+        .bind(&params.game)
+        .bind(minigame)
+        .fetch_all(&pool)
+        .await?;
+
+    let mut stats: Vec<MinigameStats> = rows.chunk_by(|a, b| a.game == b.game && a.minigame == b.minigame).map(|rows| {
+        let mut players: Vec<MinigamePlayerStats> = rows.iter().map(|row| MinigamePlayerStats {
+            player_id: row.player_id,
+            player_name: row.player_name.clone(),
+            played: row.played,
+            wins: row.wins,
+            win_rate: row.wins as f64 / row.played as f64
+        }).collect();
+        players.sort_by(|a, b| b.win_rate.total_cmp(&a.win_rate).then(b.played.cmp(&a.played)));
+
+        let key = (rows[0].game.clone(), rows[0].minigame.clone());
+        MinigameStats {
+            times_played: times_played.get(&key).copied().unwrap_or(0),
+            game: key.0,
+            minigame: key.1,
+            players
+        }
+    }).collect();
+    stats.sort_by(|a, b| b.times_played.cmp(&a.times_played).then(a.minigame.cmp(&b.minigame)));
+
+    Ok(Json(stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn game() -> MinigameGame {
+        let player = |id: i32, name: &str| PlayerResponse { id, name: name.to_string() };
+        MinigameGame {
+            game: String::from("Mario Party 2"),
+            turns: 20,
+            players: vec![player(1, "Ann"), player(2, "Bob"), player(3, "Cat"), player(4, "Dan")]
+        }
+    }
+
+    fn check(result: Value) -> Result<CheckedResult, String> {
+        check_result(&game(), &serde_json::from_value(result).unwrap())
+    }
+
+    #[test]
+    fn everyone_plays_by_default() {
+        let checked = check(json!({ "turn": 3, "minigame": "bumper balls", "type": "4P", "winners": ["Bob"] })).unwrap();
+
+        assert_eq!(checked.turn, Some(3));
+        assert_eq!(checked.minigame, "Bumper Balls");
+        assert_eq!(checked.minigame_type, "4P");
+        assert_eq!(checked.players, vec![(1, false), (2, true), (3, false), (4, false)]);
+    }
+
+    #[test]
+    fn players_by_id_or_name() {
+        let checked = check(json!({ "minigame": "Bowl Over", "type": "duel", "players": [4, "ann"], "winners": [4] })).unwrap();
+        assert_eq!(checked.players, vec![(4, true), (1, false)]);
+    }
+
+    #[test]
+    fn turn_has_to_be_in_the_game() {
+        assert_eq!(
+            check(json!({ "turn": 21, "minigame": "Bumper Balls", "type": "4P" })).err(),
+            Some(String::from("the game only had 20 turns"))
+        );
+        assert!(check(json!({ "turn": 0, "minigame": "Bumper Balls", "type": "4P" })).is_err());
+    }
+
+    #[test]
+    fn minigame_has_to_be_in_the_catalog() {
+        assert_eq!(
+            check(json!({ "minigame": "Bumper Balls 2", "type": "4P" })).err(),
+            Some(String::from("\"Bumper Balls 2\" isn't one of the minigames of Mario Party 2, see /api/minigames"))
+        );
+    }
+
+    #[test]
+    fn players_have_to_be_in_the_game() {
+        assert_eq!(
+            check(json!({ "minigame": "Bumper Balls", "type": "4P", "players": [1, 5] })).err(),
+            Some(String::from("Player 5 isn't in this game"))
+        );
+        assert!(check(json!({ "minigame": "Bumper Balls", "type": "4P", "players": [1, "Ann"] })).is_err());
+    }
+
+    #[test]
+    fn duels_are_between_two_named_players() {
+        assert!(check(json!({ "minigame": "Bowl Over", "type": "duel" })).is_err());
+        assert_eq!(
+            check(json!({ "minigame": "Bowl Over", "type": "duel", "players": [1, 2, 3] })).err(),
+            Some(String::from("a duel is between two players"))
+        );
+    }
+
+    #[test]
+    fn winners_have_to_have_played() {
+        assert_eq!(
+            check(json!({ "minigame": "Bowl Over", "type": "duel", "players": [1, 2], "winners": ["Cat"] })).err(),
+            Some(String::from("Cat won but didn't play"))
+        );
+    }
+
+    #[test]
+    fn one_vs_three_names_the_player_on_their_own() {
+        let checked = check(json!({ "minigame": "Bowl Over", "type": "1v3", "solo": "Cat", "winners": ["Cat"] })).unwrap();
+        assert_eq!(checked.solo, Some(3));
+        assert_eq!(checked.players, vec![(1, false), (2, false), (3, true), (4, false)]);
+
+        assert_eq!(
+            check(json!({ "minigame": "Bowl Over", "type": "1v3", "winners": ["Cat"] })).err(),
+            Some(String::from("say which player was on their own with solo"))
+        );
+        assert_eq!(
+            check(json!({ "minigame": "Bowl Over", "type": "4P", "solo": "Cat" })).err(),
+            Some(String::from("only a 1v3 minigame has a player on their own"))
+        );
+    }
+
+    #[test]
+    fn one_vs_three_is_won_by_one_side() {
+        assert!(check(json!({ "minigame": "Bowl Over", "type": "1v3", "solo": 3, "winners": [1, 2, 4] })).is_ok());
+        assert!(check(json!({ "minigame": "Bowl Over", "type": "1v3", "solo": 3 })).is_ok());
+
+        let wrong_side = Some(String::from("a 1v3 minigame is won by the player on their own or the team of three"));
+        assert_eq!(check(json!({ "minigame": "Bowl Over", "type": "1v3", "solo": 3, "winners": [1] })).err(), wrong_side);
+        assert_eq!(check(json!({ "minigame": "Bowl Over", "type": "1v3", "solo": 3, "winners": [1, 2, 3] })).err(), wrong_side);
+    }
+
+    #[test]
+    fn titles_without_a_catalog_take_any_name() {
+        let mut game = game();
+        game.game = String::from("Mario Party 10");
+
+        let result = serde_json::from_value(json!({ "minigame": " Whomp Maze ", "type": "4P" })).unwrap();
+        assert_eq!(check_result(&game, &result).unwrap().minigame, "Whomp Maze");
+
+        let result = serde_json::from_value(json!({ "minigame": " ", "type": "4P" })).unwrap();
+        assert_eq!(check_result(&game, &result).err(), Some(String::from("the minigame needs a name")));
+    }
+}
@@ -0,0 +1,793 @@
+use serde_json::{json, Map, Value};
+use axum::{
+    response::IntoResponse,
+    Json
+};
+use crate::listfields::{EnumData, Field, ListFields, ObjectData};
+use crate::requests::{GameData, MarioPartyData};
+
+/// Turns `ListFields` output into JSON Schema. Every type with its own
+/// ListFields (the entry structs, the *Characters enums, ...) ends up in
+/// `components` once and is referenced from everywhere else with $ref.
+#[derive(Default)]
+struct SchemaBuilder {
+    components: Map<String, Value>
+}
+
+fn component_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+// The JSON Schema for the types that don't implement ListFields
+fn primitive_schema(ty: &str) -> Value {
+    match ty {
+        "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" => json!({ "type": "integer" }),
+        "f32" | "f64" => json!({ "type": "number" }),
+        "bool" => json!({ "type": "boolean" }),
+        "DateTime" => json!({ "type": "string", "format": "date-time" }),
+        _ => json!({ "type": "string" })
+    }
+}
+
+impl SchemaBuilder {
+    fn add_component(&mut self, name: &str, data: &ObjectData) {
+        if !self.components.contains_key(name) {
+            let schema = self.object_schema(data);
+            self.components.insert(name.to_string(), schema);
+        }
+    }
+
+    fn object_schema(&mut self, data: &ObjectData) -> Value {
+        match data {
+            ObjectData::Fields(fields) => {
+                let mut properties = Map::new();
+                let mut required = Vec::new();
+                for field in fields {
+                    properties.insert(field.name.clone(), self.field_schema(field));
+                    if !field.optional {
+                        required.push(field.name.clone());
+                    }
+                }
+
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required
+                })
+            },
+            ObjectData::EnumData(EnumData { name: _, variants }) => {
+                if variants.iter().all(|variant| variant.type_data.is_none()) {
+                    let names: Vec<&str> = variants.iter().map(|variant| variant.name.as_str()).collect();
+                    return json!({ "type": "string", "enum": names });
+                }
+
+                // serde's default, externally tagged, layout. MarioPartyData
+                // isn't laid out like this, see game_data_schema
+                let one_of: Vec<Value> = variants.iter().map(|variant| {
+                    let schema = match (&variant.ty, &variant.type_data) {
+                        (Some(ty), Some(type_data)) => {
+                            self.add_component(ty, type_data);
+                            component_ref(ty)
+                        },
+                        _ => json!({})
+                    };
+                    json!({
+                        "type": "object",
+                        "properties": { variant.name.clone(): schema },
+                        "required": [variant.name]
+                    })
+                }).collect();
+
+                json!({ "oneOf": one_of })
+            }
+        }
+    }
+
+    fn field_schema(&mut self, field: &Field) -> Value {
+        let mut schema = match &field.type_data {
+            Some(type_data) => {
+                self.add_component(&field.ty, type_data);
+                component_ref(&field.ty)
+            },
+            None => primitive_schema(&field.ty)
+        };
+
+        let (min_key, max_key) = if field.list {
+            schema = json!({ "type": "array", "items": schema });
+            ("minItems", "maxItems")
+        } else {
+            ("minimum", "maximum")
+        };
+
+        if let Some(object) = schema.as_object_mut() {
+            if let Some(min) = field.min {
+                object.insert(String::from(min_key), json!(min));
+            }
+            if let Some(max) = field.max {
+                object.insert(String::from(max_key), json!(max));
+            }
+            if field.per_turn {
+                object.insert(String::from("description"), json!("Can't be more than the game's number of turns"));
+            }
+        }
+
+        schema
+    }
+
+    /// The schema for `GameData`.
+    ///
+    /// This one is put together by hand because of the serde attributes
+    /// ListFields doesn't know about: `MarioPartyData` is adjacently tagged
+    /// with "game" and "player_data", and flattened into `GameData`. So each
+    /// game is its own object, with the rest of GameData's fields next to
+    /// "game" and "player_data".
+    fn game_data_schema(&mut self) -> Value {
+        let mut common = Map::new();
+        let mut common_required = Vec::new();
+        if let ObjectData::Fields(fields) = GameData::list_fields() {
+            for field in fields.iter().filter(|field| field.name != "player_data") {
+                common.insert(field.name.clone(), self.field_schema(field));
+                if !field.optional {
+                    common_required.push(field.name.clone());
+                }
+            }
+        }
+
+        let mut one_of = Vec::new();
+        let mut mapping = Map::new();
+        if let ObjectData::EnumData(EnumData { name: _, variants }) = MarioPartyData::list_fields() {
+            for variant in variants {
+                let (Some(ty), Some(type_data)) = (&variant.ty, &variant.type_data) else {
+                    continue;
+                };
+                self.add_component(ty, type_data);
+
+                let mut player_data = json!({ "type": "array", "items": component_ref(ty) });
+                if let Some(min) = variant.min {
+                    player_data["minItems"] = json!(min);
+                }
+                if let Some(max) = variant.max {
+                    player_data["maxItems"] = json!(max);
+                }
+
+                let mut properties = common.clone();
+                properties.insert(String::from("game"), json!({ "const": variant.name }));
+                properties.insert(String::from("player_data"), player_data);
+
+                let mut required = vec![String::from("game"), String::from("player_data")];
+                required.extend(common_required.iter().cloned());
+
+                let title = format!("{}Game", ty);
+                self.components.insert(title.clone(), json!({
+                    "title": variant.name,
+                    "type": "object",
+                    "properties": properties,
+                    "required": required
+                }));
+                mapping.insert(variant.name.clone(), json!(format!("#/components/schemas/{title}")));
+                one_of.push(component_ref(&title));
+            }
+        }
+
+        json!({
+            "oneOf": one_of,
+            "discriminator": { "propertyName": "game", "mapping": mapping }
+        })
+    }
+}
+
+// The request body of the endpoints that take a game
+fn game_body() -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": component_ref("GameData") } }
+    })
+}
+
+// The request body of the endpoints that make or rename a group
+fn group_body() -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": component_ref("GroupData") } }
+    })
+}
+
+// The request body of the endpoints that take a username and password
+fn credentials_body() -> Value {
+    json!({
+        "required": true,
+        "content": { "application/x-www-form-urlencoded": { "schema": component_ref("Credentials") } }
+    })
+}
+
+// A response described only by its description, with the JSON left unspecified
+fn json_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": {} } }
+    })
+}
+
+// The MessageResponse that every endpoint can fail with, and some succeed with
+fn message_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": component_ref("MessageResponse") } }
+    })
+}
+
+fn operation(summary: &str, success: Value) -> Value {
+    json!({
+        "summary": summary,
+        "responses": {
+            "200": success,
+            "default": message_response("Something went wrong")
+        }
+    })
+}
+
+fn with_body(mut operation: Value, body: Value) -> Value {
+    operation["requestBody"] = body;
+    operation
+}
+
+// Endpoints that work on a group's games and players take ?group=, the
+// user's personal group when it's left out
+fn with_group(mut operation: Value) -> Value {
+    operation["parameters"] = query_parameters(&[("group", "i32")]);
+    operation
+}
+
+fn id_parameter() -> Value {
+    json!([{ "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }])
+}
+
+fn query_parameters(names: &[(&str, &str)]) -> Value {
+    let parameters: Vec<Value> = names.iter().map(|(name, ty)| {
+        json!({ "name": name, "in": "query", "required": false, "schema": primitive_schema(ty) })
+    }).collect();
+
+    json!(parameters)
+}
+
+// Kept apart from paths, which json! can't take all at once
+fn group_paths() -> Value {
+    json!({
+        "/api/groups": {
+            "get": operation("The groups the user is in", json_response("Each group's id, name and the user's role in it")),
+            "post": with_body(operation("Make a group, owned by the user", json_response("The new group")), group_body())
+        },
+        "/api/groups/{id}": {
+            "parameters": id_parameter(),
+            "get": operation("A group the user is in", json_response("The group and its members")),
+            "put": with_body(operation("Rename a group", message_response("Renamed group")), group_body()),
+            "delete": operation("Delete a group along with its games and players", message_response("Deleted group"))
+        },
+        "/api/groups/{id}/members/{user_id}": {
+            "parameters": [
+                { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } },
+                { "name": "user_id", "in": "path", "required": true, "schema": { "type": "integer" } }
+            ],
+            "put": with_body(operation("Change a member's role", message_response("Changed role")), json!({
+                "required": true,
+                "content": { "application/json": { "schema": component_ref("MemberRole") } }
+            })),
+            "delete": operation("Remove a member, or leave the group", message_response("Removed member"))
+        },
+        "/api/groups/{id}/invitations": {
+            "parameters": id_parameter(),
+            "get": operation("The group's invitations that haven't been answered", json_response("The invitations")),
+            "post": with_body(operation("Invite someone to the group by their username", json_response("The invitation")), json!({
+                "required": true,
+                "content": { "application/json": { "schema": component_ref("InvitationData") } }
+            }))
+        },
+        "/api/invitations": {
+            "get": operation("The user's invitations that haven't been answered", json_response("The invitations"))
+        },
+        "/api/invitations/{id}": {
+            "parameters": id_parameter(),
+            "delete": operation("Decline an invitation, or take one back", message_response("Deleted invitation"))
+        },
+        "/api/invitations/{id}/accept": {
+            "parameters": id_parameter(),
+            "post": operation("Accept an invitation and join the group", message_response("Joined group"))
+        }
+    })
+}
+
+// Only for users with the admin role
+fn admin_paths() -> Value {
+    json!({
+        "/api/admin/users": {
+            "get": operation("Every user, oldest first", json_response("Each user's id, username, role and whether they're disabled"))
+        },
+        "/api/admin/users/{id}": {
+            "parameters": id_parameter(),
+            "put": with_body(operation("Change a user's role, or disable or enable them", json_response("The user")), json!({
+                "required": true,
+                "content": { "application/json": { "schema": component_ref("UserUpdate") } }
+            })),
+            "delete": operation("Delete a user and their personal group", message_response("Deleted user"))
+        }
+    })
+}
+
+fn paths() -> Value {
+    let mut paths = json!({
+        "/api/signup": {
+            "post": with_body(operation("Create an account and log in to it", message_response("Signed up")), credentials_body())
+        },
+        "/api/login": {
+            "post": with_body(operation("Log in", message_response("Logged in")), credentials_body())
+        },
+        "/api/logout": {
+            "post": operation("Log out", message_response("Logged out"))
+        },
+        "/api/me": {
+            "get": operation("The logged in user", json_response("The user's id, username and role"))
+        },
+        "/api/me/password": {
+            "post": with_body(
+                operation("Change the password, logging out every other session", message_response("Password changed")),
+                json!({
+                    "required": true,
+                    "content": { "application/x-www-form-urlencoded": { "schema": component_ref("PasswordChange") } }
+                })
+            )
+        },
+        "/api/me/backup": {
+            "get": with_group(operation("Back up one group, the given one or the user's personal group: every game and player in it, with each game's turns and minigames, to restore with /api/me/restore. Each of the user's groups is backed up on its own", json_response("A versioned backup of the group, naming it")))
+        },
+        "/api/me/restore": {
+            "post": {
+                "summary": "Restore a backup of a group from /api/me/backup into the given group, merging it with what's already there or replacing it",
+                "parameters": query_parameters(&[("mode", "String"), ("group", "i32")]),
+                "requestBody": { "required": true, "content": { "application/json": { "schema": {} } } },
+                "responses": {
+                    "200": json_response("How many players and games were restored, and the games that were left out because they didn't pass the checks new games get"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/games": {
+            "get": {
+                "summary": "The group's games, newest first unless sort is asc",
+                "parameters": query_parameters(&[("page", "i64"), ("per_page", "i64"), ("sort", "String"), ("group", "i32")]),
+                "responses": {
+                    "200": json_response("A page of games, each shaped like GameData plus its id"),
+                    "default": message_response("Something went wrong")
+                }
+            },
+            "post": with_body(with_group(operation("Submit a game", message_response("Created game"))), game_body())
+        },
+        "/api/games/{id}": {
+            "parameters": id_parameter(),
+            "get": operation("One game, shaped like GameData plus its id", json_response("The game")),
+            "put": with_body(operation("Replace a game", message_response("Updated game")), game_body()),
+            "delete": operation("Delete a game", message_response("Deleted game"))
+        },
+        "/api/games/{id}/turns": {
+            "parameters": id_parameter(),
+            "get": operation(
+                "A game turn by turn, and whether everyone's last turn matches their entry",
+                json_response("The turns, oldest first, and anything that doesn't match")
+            ),
+            "put": with_body(
+                operation("Replace a game's turns", json_response("The new turns, and anything that doesn't match")),
+                json!({
+                    "required": true,
+                    "content": { "application/json": { "schema": { "type": "array", "items": component_ref("TurnSnapshot") } } }
+                })
+            )
+        },
+        "/api/games/{id}/minigames": {
+            "parameters": id_parameter(),
+            "get": operation("The minigames played in a game", json_response("The minigames, by turn")),
+            "put": with_body(
+                operation("Replace a game's minigames", json_response("The new minigames")),
+                json!({
+                    "required": true,
+                    "content": { "application/json": { "schema": { "type": "array", "items": component_ref("MinigameResultData") } } }
+                })
+            )
+        },
+        "/api/players": {
+            "get": with_group(operation("The group's players", json_response("Every player's id and name"))),
+            "post": with_body(with_group(operation("Add a player", json_response("The new player"))), json!({
+                "required": true,
+                "content": { "application/json": { "schema": component_ref("PlayerData") } }
+            }))
+        },
+        "/api/players/{id}": {
+            "parameters": id_parameter(),
+            "get": operation("One player", json_response("The player")),
+            "put": with_body(operation("Rename a player", json_response("The renamed player")), json!({
+                "required": true,
+                "content": { "application/json": { "schema": component_ref("PlayerData") } }
+            })),
+            "delete": operation("Delete a player that isn't in any games", message_response("Deleted player"))
+        },
+        "/api/stats/leaderboard": {
+            "get": {
+                "summary": "Wins, average placement and average stars per player",
+                "parameters": query_parameters(&[("game", "String"), ("from", "DateTime"), ("to", "DateTime"), ("group", "i32")]),
+                "responses": {
+                    "200": json_response("One row per player, most wins first"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/stats/characters": {
+            "get": {
+                "summary": "Pick rate, win rate, average stars and average coins for every character in one game",
+                "parameters": query_parameters(&[("game", "String"), ("from", "DateTime"), ("to", "DateTime"), ("group", "i32")]),
+                "responses": {
+                    "200": json_response("One row per character, in the game's order, including ones never played"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/stats/h2h": {
+            "get": {
+                "summary": "Every game two players were both in, and their record against each other",
+                "parameters": query_parameters(&[("a", "String"), ("b", "String"), ("game", "String"), ("from", "DateTime"), ("to", "DateTime"), ("group", "i32")]),
+                "responses": {
+                    "200": json_response("Wins, losses and ties from a's side, the average star differential and the games"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/stats/spaces": {
+            "get": {
+                "summary": "Each player's landings per turn on every kind of space, and how each kind goes with placement",
+                "parameters": query_parameters(&[("game", "String"), ("from", "DateTime"), ("to", "DateTime"), ("group", "i32")]),
+                "responses": {
+                    "200": json_response("One object per game that's been played, or just the game asked for"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/stats/minigames": {
+            "get": {
+                "summary": "How often each player wins every minigame they've played",
+                "parameters": query_parameters(&[("game", "String"), ("minigame", "String"), ("group", "i32")]),
+                "responses": {
+                    "200": json_response("One object per game and minigame, most played first, best win rate first inside each"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/minigames": {
+            "get": {
+                "summary": "The minigames of every game, or of one",
+                "parameters": query_parameters(&[("game", "String")]),
+                "responses": {
+                    "200": json_response("One object per game with its minigames. Games that aren't listed take any minigame name"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/ratings": {
+            "get": {
+                "summary": "Everyone's current Elo rating, in one game or across every game, highest first",
+                "parameters": query_parameters(&[("game", "String"), ("group", "i32")]),
+                "responses": {
+                    "200": json_response("One row per rated player"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/ratings/{player_id}": {
+            "get": {
+                "summary": "A player's rating after each of their games, in one game or across every game",
+                "parameters": [
+                    { "name": "player_id", "in": "path", "required": true, "schema": { "type": "integer" } },
+                    { "name": "game", "in": "query", "required": false, "schema": { "type": "string" } }
+                ],
+                "responses": {
+                    "200": json_response("The player and their rating history, oldest first"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/live": {
+            "get": with_group(operation("The games being played right now", json_response("Each game's id and state so far"))),
+            "post": with_body(with_group(operation("Start a game to enter turn by turn", json_response("The new game's id and state"))), json!({
+                "required": true,
+                "content": { "application/json": { "schema": component_ref("LiveGameStart") } }
+            }))
+        },
+        "/api/live/{id}": {
+            "parameters": id_parameter(),
+            "get": operation("A game being played", json_response("The game's id and state so far")),
+            "delete": operation("Throw away a game being played", message_response("Discarded game"))
+        },
+        "/api/live/{id}/ws": {
+            "parameters": id_parameter(),
+            "get": {
+                "summary": "A websocket that sends the game's state after every change and takes delta and set messages",
+                "responses": {
+                    "101": { "description": "Switching to the websocket" },
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/live/{id}/finalize": {
+            "parameters": id_parameter(),
+            "post": operation("Finish a game being played, checking it like POST /api/games", message_response("Finished game"))
+        },
+        "/api/export.csv": {
+            "get": {
+                "summary": "Every player entry of one game, with the game's columns first",
+                "parameters": query_parameters(&[("game", "String"), ("group", "i32")]),
+                "responses": {
+                    "200": { "description": "A CSV file", "content": { "text/csv": { "schema": { "type": "string" } } } },
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/export.zip": {
+            "get": {
+                "summary": "A CSV like /api/export.csv for every game that has been played",
+                "parameters": query_parameters(&[("group", "i32")]),
+                "responses": {
+                    "200": { "description": "A ZIP file", "content": { "application/zip": { "schema": { "type": "string", "format": "binary" } } } },
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/import": {
+            "post": {
+                "summary": "Import games from a CSV laid out like /api/export.csv, or JSON Lines of GameData",
+                "parameters": query_parameters(&[("format", "String"), ("dry_run", "bool"), ("group", "i32")]),
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "text/csv": { "schema": { "type": "string" } },
+                        "application/x-ndjson": { "schema": { "type": "string" } }
+                    }
+                },
+                "responses": {
+                    "200": json_response("Whether each game was accepted, and why not if it wasn't"),
+                    "default": message_response("Something went wrong")
+                }
+            }
+        },
+        "/api/input/schema": {
+            "get": operation("The fields of each game's player data, for building forms", json_response("Fields by game name"))
+        },
+        "/api/openapi.json": {
+            "get": operation("This document", json_response("An OpenAPI document"))
+        }
+    });
+
+    for more_paths in [group_paths(), admin_paths()] {
+        if let (Some(paths), Value::Object(more_paths)) = (paths.as_object_mut(), more_paths) {
+            paths.extend(more_paths);
+        }
+    }
+
+    paths
+}
+
+/// The OpenAPI document for the whole API. The game schemas come from
+/// ListFields, so they change whenever requests.rs does.
+fn openapi_document() -> Value {
+    let mut builder = SchemaBuilder::default();
+    let game_data = builder.game_data_schema();
+    builder.components.insert(String::from("GameData"), game_data);
+
+    builder.components.insert(String::from("MessageResponse"), json!({
+        "type": "object",
+        "properties": {
+            "message": { "type": "string" },
+            "success": { "type": "boolean" }
+        },
+        "required": ["message", "success"]
+    }));
+    builder.components.insert(String::from("Credentials"), json!({
+        "type": "object",
+        "properties": {
+            "username": { "type": "string" },
+            "password": { "type": "string" }
+        },
+        "required": ["username", "password"]
+    }));
+    builder.components.insert(String::from("PasswordChange"), json!({
+        "type": "object",
+        "properties": {
+            "current_password": { "type": "string" },
+            "new_password": { "type": "string" }
+        },
+        "required": ["current_password", "new_password"]
+    }));
+    builder.components.insert(String::from("LiveGameStart"), json!({
+        "type": "object",
+        "properties": {
+            "game": { "type": "string" },
+            "board": { "type": "string" },
+            "turns": { "type": "integer" },
+            "date": { "type": "string", "format": "date-time" },
+            "players": {
+                "type": "array",
+                "items": { "type": "object", "description": "The start of the player's entry, numbers left out start at 0" }
+            }
+        },
+        "required": ["game", "board", "turns", "players"]
+    }));
+    builder.components.insert(String::from("TurnSnapshot"), json!({
+        "type": "object",
+        "properties": {
+            "turn": { "type": "integer", "minimum": 1 },
+            "player_id": { "type": "integer" },
+            "player_name": { "type": "string" },
+            "stars": { "type": "integer", "minimum": 0 },
+            "coins": { "type": "integer", "minimum": 0 },
+            "space": { "type": "string", "description": "The *_spaces field of the space the player landed on" }
+        },
+        "required": ["turn"]
+    }));
+    builder.components.insert(String::from("MinigameResultData"), json!({
+        "type": "object",
+        "properties": {
+            "turn": { "type": "integer", "minimum": 1 },
+            "minigame": { "type": "string", "description": "One of the game's minigames from /api/minigames, or any name for a game that isn't listed there" },
+            "type": { "type": "string", "enum": ["4P", "1v3", "2v2", "duel", "battle"] },
+            "players": {
+                "type": "array",
+                "items": { "type": ["integer", "string"] },
+                "description": "Ids or names of who played, everyone in the game if left out"
+            },
+            "winners": { "type": "array", "items": { "type": ["integer", "string"] } },
+            "solo": {
+                "type": ["integer", "string"],
+                "description": "Id or name of the player on their own, only and always for 1v3"
+            }
+        },
+        "required": ["minigame", "type"]
+    }));
+    builder.components.insert(String::from("GroupData"), json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" }
+        },
+        "required": ["name"]
+    }));
+    builder.components.insert(String::from("InvitationData"), json!({
+        "type": "object",
+        "properties": {
+            "username": { "type": "string" }
+        },
+        "required": ["username"]
+    }));
+    builder.components.insert(String::from("MemberRole"), json!({
+        "type": "object",
+        "properties": {
+            "role": { "type": "string", "enum": ["owner", "member"] }
+        },
+        "required": ["role"]
+    }));
+    builder.components.insert(String::from("UserUpdate"), json!({
+        "type": "object",
+        "properties": {
+            "role": { "type": "string", "enum": ["admin", "user", "read-only"] },
+            "disabled": { "type": "boolean" }
+        }
+    }));
+    builder.components.insert(String::from("PlayerData"), json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" }
+        },
+        "required": ["name"]
+    }));
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Mario Party Tracker",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": paths(),
+        "components": {
+            "schemas": builder.components,
+            "securitySchemes": {
+                "session": { "type": "apiKey", "in": "cookie", "name": "id" }
+            }
+        },
+        "security": [{ "session": [] }]
+    })
+}
+
+#[axum::debug_handler]
+pub async fn openapi() -> impl IntoResponse {
+    Json(openapi_document())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every "$ref" anywhere under `value`
+    fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(reference)) = object.get("$ref") {
+                    found.push(reference);
+                }
+                object.values().for_each(|value| refs(value, found));
+            },
+            Value::Array(items) => items.iter().for_each(|value| refs(value, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn every_ref_has_a_component() {
+        let document = openapi_document();
+        let mut found = Vec::new();
+        refs(&document, &mut found);
+
+        assert!(!found.is_empty());
+        for reference in found {
+            let name = reference.strip_prefix("#/components/schemas/").unwrap();
+            assert!(document["components"]["schemas"].get(name).is_some(), "{reference} isn't a component");
+        }
+    }
+
+    #[test]
+    fn every_operation_has_a_summary_and_a_success_response() {
+        let document = openapi_document();
+        let paths = document["paths"].as_object().unwrap();
+
+        assert!(paths.contains_key("/api/games"));
+        for (path, methods) in paths {
+            // Next to the methods there can be parameters shared by all of them
+            for (method, operation) in methods.as_object().unwrap().iter().filter(|(key, _)| *key != "parameters") {
+                assert!(operation["summary"].is_string(), "{method} {path} has no summary");
+                // 200 for most, 101 for the websocket
+                let responses = operation["responses"].as_object().unwrap();
+                assert!(responses.keys().any(|status| status == "200" || status == "101"), "{method} {path} has no success response");
+            }
+        }
+    }
+
+    #[test]
+    fn game_data_has_one_schema_per_title() {
+        let document = openapi_document();
+        let game_data = &document["components"]["schemas"]["GameData"];
+        let titles = match MarioPartyData::list_fields() {
+            ObjectData::EnumData(EnumData { name: _, variants }) => variants.len(),
+            ObjectData::Fields(_) => panic!("MarioPartyData should be an enum")
+        };
+
+        assert_eq!(game_data["oneOf"].as_array().unwrap().len(), titles);
+        assert_eq!(game_data["discriminator"]["propertyName"], "game");
+        assert_eq!(
+            game_data["discriminator"]["mapping"]["Mario Party 2"],
+            "#/components/schemas/MarioParty2Game"
+        );
+
+        let game = &document["components"]["schemas"]["MarioParty2Game"];
+        assert_eq!(game["properties"]["game"]["const"], "Mario Party 2");
+        assert_eq!(game["properties"]["player_data"]["minItems"], 1);
+        assert_eq!(game["properties"]["player_data"]["maxItems"], 4);
+        assert_eq!(game["properties"]["turns"]["type"], "integer");
+        assert!(game["required"].as_array().unwrap().contains(&json!("board")));
+    }
+
+    #[test]
+    fn field_limits_become_schema_limits() {
+        let document = openapi_document();
+        let entry = &document["components"]["schemas"]["MarioParty2"];
+
+        assert_eq!(entry["properties"]["placement"]["minimum"], 1);
+        assert_eq!(entry["properties"]["placement"]["maximum"], 4);
+        assert_eq!(entry["properties"]["blue_spaces"]["minimum"], 0);
+        assert!(entry["properties"]["blue_spaces"]["description"].is_string());
+        assert_eq!(entry["properties"]["bonus_stars"]["type"], "array");
+        assert_eq!(entry["properties"]["character"]["$ref"], "#/components/schemas/MarioParty2Characters");
+        assert!(!entry["required"].as_array().unwrap().contains(&json!("player_id")));
+    }
+}
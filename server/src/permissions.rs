@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use async_trait::async_trait;
+use axum::{
+    extract::Request,
+    middleware::Next,
+    response::Response
+};
+use axum_login::AuthzBackend;
+use crate::requests::Role;
+use crate::errors::ApiError;
+use crate::routes::{AuthSession, Backend, Error, User};
+
+/// What the route layers below check for. Which groups a user can see is
+/// still up to the handlers, these only say what kind of thing they can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    // Looking at the games, players and stats of their groups
+    Read,
+    // Adding to and changing them, and running the groups themselves
+    Write,
+    // Listing, disabling and deleting other users
+    Admin
+}
+
+fn role_permissions(role: Role) -> HashSet<Permission> {
+    match role {
+        Role::Admin => HashSet::from([Permission::Read, Permission::Write, Permission::Admin]),
+        Role::User => HashSet::from([Permission::Read, Permission::Write]),
+        Role::ReadOnly => HashSet::from([Permission::Read])
+    }
+}
+
+#[async_trait]
+impl AuthzBackend for Backend {
+    type Permission = Permission;
+
+    async fn get_user_permissions(&self, user: &User) -> Result<HashSet<Permission>, Error> {
+        Ok(role_permissions(user.role))
+    }
+}
+
+/// Whether the signed in user has a permission
+pub async fn has_permission(auth_session: &AuthSession, permission: Permission) -> Result<bool, ApiError> {
+    let Some(user) = &auth_session.user else {
+        println!("No user signed in");
+        return Err(ApiError::Unauthorized(String::from("Not signed in")));
+    };
+
+    Ok(auth_session.backend.has_perm(user, permission).await.map_err(axum_login::Error::Backend)?)
+}
+
+async fn require(auth_session: AuthSession, permission: Permission, request: Request, next: Next, message: &str) -> Result<Response, ApiError> {
+    if !has_permission(&auth_session, permission).await? {
+        println!("Missing the {:?} permission", permission);
+        return Err(ApiError::Forbidden(String::from(message)));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Route layer for everything that changes a group or what's in it
+pub async fn require_write(auth_session: AuthSession, request: Request, next: Next) -> Result<Response, ApiError> {
+    require(auth_session, Permission::Write, request, next, "Your account is read-only").await
+}
+
+/// Route layer for managing other users
+pub async fn require_admin(auth_session: AuthSession, request: Request, next: Next) -> Result<Response, ApiError> {
+    require(auth_session, Permission::Admin, request, next, "Only admins can do that").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::postgres::PgPool;
+
+    fn user(role: &str) -> User {
+        serde_json::from_value(json!({
+            "id": 1,
+            "username": "someone",
+            "password_hash": "",
+            "role": role,
+            "disabled": false
+        })).unwrap()
+    }
+
+    #[test]
+    fn each_role_has_the_ones_below_it() {
+        assert_eq!(role_permissions(Role::ReadOnly), HashSet::from([Permission::Read]));
+        assert_eq!(role_permissions(Role::User), HashSet::from([Permission::Read, Permission::Write]));
+        assert_eq!(role_permissions(Role::Admin), HashSet::from([Permission::Read, Permission::Write, Permission::Admin]));
+    }
+
+    #[tokio::test]
+    async fn backend_checks_the_users_role() {
+        // Permissions come from the user's role, so the database is never used
+        let backend = Backend::new(PgPool::connect_lazy("postgres://localhost/unused").unwrap());
+
+        assert!(backend.has_perm(&user("read-only"), Permission::Read).await.unwrap());
+        assert!(!backend.has_perm(&user("read-only"), Permission::Write).await.unwrap());
+        assert!(backend.has_perm(&user("user"), Permission::Write).await.unwrap());
+        assert!(!backend.has_perm(&user("user"), Permission::Admin).await.unwrap());
+        assert!(backend.has_perm(&user("admin"), Permission::Admin).await.unwrap());
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+use axum_login::AuthUser;
+use sqlx::postgres::{PgConnection, PgPool};
+use axum::{
+    Extension,
+    extract::{Path, Query},
+    response::IntoResponse,
+    Json
+};
+use crate::requests::{GroupQuery, MarioPartyData, PlayerData};
+use crate::responses::{MessageResponse, PlayerResponse};
+use crate::errors::ApiError;
+use crate::routes::{AuthSession, signed_in_user};
+use crate::groups::member_group;
+
+// Stray spaces are how "Jon" and "jon " ended up as different people,
+// so names are always stored trimmed
+fn clean_name(name: &str) -> Result<&str, ApiError> {
+    let name = name.trim();
+    if name.is_empty() {
+        Err(ApiError::BadRequest(String::from("Player names can't be empty")))
+    }
+    else {
+        Ok(name)
+    }
+}
+
+/// Fills in the player_id of every player in a game, creating a player for
+/// any name the group hasn't used before. Players only given by id get their
+/// current name as their player_name for the game.
+pub async fn resolve_players(conn: &mut PgConnection, group_id: i32, player_data: &mut MarioPartyData) -> Result<(), ApiError> {
+    let mut seen = HashSet::new();
+
+    for (player_id, player_name) in player_data.players_mut() {
+        let player: PlayerResponse = match (*player_id, player_name.as_deref()) {
+            (Some(id), _) => {
+                sqlx::query_as("SELECT id, name FROM Players WHERE id = $1 AND group_id = $2")
+                    .bind(id)
+                    .bind(group_id)
+                    .fetch_optional(&mut *conn)
+                    .await?
+                    .ok_or_else(|| ApiError::BadRequest(format!("There's no player with id {id}")))?
+            },
+            (None, Some(name)) => {
+                // The do-nothing update is so RETURNING gives back the player
+                // that already has this name
+                sqlx::query_as("
+                        INSERT INTO Players (group_id, name) VALUES ($1, $2)
+                        ON CONFLICT (group_id, lower(name)) DO UPDATE SET name = Players.name
+                        RETURNING id, name")
+                    .bind(group_id)
+                    .bind(clean_name(name)?)
+                    .fetch_one(&mut *conn)
+                    .await?
+            },
+            (None, None) => {
+                return Err(ApiError::BadRequest(String::from("Every player needs a player_id or a player_name")));
+            }
+        };
+
+        if !seen.insert(player.id) {
+            return Err(ApiError::BadRequest(format!("{} is in the game more than once", player.name)));
+        }
+
+        // The name typed for this game is kept as it was, it's only what
+        // the player went by that night
+        let alias = match player_name.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => player.name
+        };
+
+        *player_id = Some(player.id);
+        *player_name = Some(alias);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler]
+pub async fn list_players(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    let players: Vec<PlayerResponse> = sqlx::query_as("SELECT id, name FROM Players WHERE group_id = $1 ORDER BY lower(name)")
+        .bind(group_id)
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(players))
+}
+
+#[axum::debug_handler]
+pub async fn player(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(player_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let player: PlayerResponse = sqlx::query_as("
+            SELECT id, name FROM Players
+            WHERE id = $1 AND group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $2)")
+        .bind(player_id)
+        .bind(user.id())
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(String::from("Player not found")))?;
+
+    Ok(Json(player))
+}
+
+#[axum::debug_handler]
+pub async fn create_player(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Json(player_data): Json<PlayerData>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+    let name = clean_name(&player_data.name)?;
+
+    let player: PlayerResponse = sqlx::query_as("INSERT INTO Players (group_id, name) VALUES ($1, $2) RETURNING id, name")
+        .bind(group_id)
+        .bind(name)
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(Json(player))
+}
+
+#[axum::debug_handler]
+pub async fn update_player(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(player_id): Path<i32>,
+    Json(player_data): Json<PlayerData>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let name = clean_name(&player_data.name)?;
+
+    let player: PlayerResponse = sqlx::query_as("
+            UPDATE Players SET name = $1
+            WHERE id = $2 AND group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $3)
+            RETURNING id, name")
+        .bind(name)
+        .bind(player_id)
+        .bind(user.id())
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(String::from("Player not found")))?;
+
+    Ok(Json(player))
+}
+
+#[axum::debug_handler]
+pub async fn delete_player(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(player_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let result = sqlx::query("
+            DELETE FROM Players
+            WHERE id = $1 AND group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $2)")
+        .bind(player_id)
+        .bind(user.id())
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(String::from("Player not found")));
+    }
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Deleted player successfully"),
+            success: true
+        }
+    ))
+}
@@ -0,0 +1,309 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgConnection, PgPool};
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::{Path, Query},
+    response::IntoResponse,
+    Json
+};
+use crate::requests::{GroupQuery, RatingsQuery};
+use crate::responses::{PlayerResponse, RatingEntry, RatingHistoryResponse, RatingPoint};
+use crate::errors::ApiError;
+use crate::routes::{AuthSession, signed_in_user};
+use crate::groups::member_group;
+use crate::stats::{ranked_entries_query, scorings_for, SCORING};
+
+/// Where everyone starts, before their first game
+const INITIAL_RATING: f64 = 1500.0;
+/// The most one game can move a rating by
+const K_FACTOR: f64 = 32.0;
+
+/// How much each player's rating moves after a game, given everyone's
+/// rating going in and where they placed.
+///
+/// Every pair of players is scored like a one-on-one Elo match, where
+/// finishing ahead is a win and placing the same is a draw. The K factor is
+/// shared out over the n - 1 opponents, so coming first in a four player
+/// game moves a rating about as much as winning one match would.
+fn rating_changes(players: &[(f64, i32)]) -> Vec<f64> {
+    if players.len() < 2 {
+        return vec![0.0; players.len()];
+    }
+
+    let k = K_FACTOR / (players.len() - 1) as f64;
+    players.iter().enumerate().map(|(i, (rating, placement))| {
+        let score: f64 = players.iter().enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, (other_rating, other_placement))| {
+                let expected = 1.0 / (1.0 + 10f64.powf((other_rating - rating) / 400.0));
+                let actual = match placement.cmp(other_placement) {
+                    Ordering::Less => 1.0,
+                    Ordering::Equal => 0.5,
+                    Ordering::Greater => 0.0
+                };
+                actual - expected
+            })
+            .sum();
+
+        k * score
+    }).collect()
+}
+
+#[derive(sqlx::FromRow)]
+struct ReplayEntry {
+    game_id: i32,
+    game: String,
+    player_id: i32,
+    placement: i32
+}
+
+/// Replays every one of the group's games played at or after `from`, in date
+/// order, and rewrites their RatingHistory. Games before `from` are left
+/// alone and the ratings they ended on are where the replay starts.
+///
+/// Run this in the same transaction as whatever added, changed or removed
+/// games, with `from` being the earliest date that was touched. A game added
+/// after every other game only replays that one game.
+pub async fn recompute_from(conn: &mut PgConnection, group_id: i32, from: DateTime<Utc>) -> Result<(), sqlx::Error> {
+    sqlx::query("
+            DELETE FROM RatingHistory
+            USING Games
+            WHERE Games.id = RatingHistory.game_id AND Games.group_id = $1 AND Games.date >= $2")
+        .bind(group_id)
+        .bind(from)
+        .execute(&mut *conn)
+        .await?;
+
+    // Where everyone's ratings stand before `from`, by player and title.
+    // A None title is the global rating
+    let current: Vec<(i32, Option<String>, f64)> = sqlx::query_as("
+            SELECT DISTINCT ON (r.player_id, r.game) r.player_id, r.game, r.rating
+            FROM RatingHistory r
+                JOIN Games ON Games.id = r.game_id
+            WHERE Games.group_id = $1
+            ORDER BY r.player_id, r.game, Games.date DESC, Games.id DESC")
+        .bind(group_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let mut ratings: HashMap<(i32, Option<String>), f64> = current.into_iter()
+        .map(|(player_id, game, rating)| ((player_id, game), rating))
+        .collect();
+
+    let scorings: Vec<_> = SCORING.iter().collect();
+    let entries: Vec<ReplayEntry> = sqlx::query_as(&format!("
+            WITH entries AS ({})
+            SELECT game_id, game, player_id, placement FROM entries
+            ORDER BY date, game_id",
+            ranked_entries_query(&scorings)))
+        .bind(group_id)
+        .bind(from)
+        .bind(None::<DateTime<Utc>>)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut player_ids = Vec::new();
+    let mut game_ids = Vec::new();
+    let mut games = Vec::new();
+    let mut new_ratings = Vec::new();
+    let mut changes = Vec::new();
+    for game in entries.chunk_by(|a, b| a.game_id == b.game_id) {
+        for scope in [None, Some(game[0].game.clone())] {
+            let before: Vec<(f64, i32)> = game.iter().map(|entry| {
+                let rating = ratings.get(&(entry.player_id, scope.clone())).copied().unwrap_or(INITIAL_RATING);
+                (rating, entry.placement)
+            }).collect();
+
+            for (entry, ((rating, _), change)) in game.iter().zip(before.iter().zip(rating_changes(&before))) {
+                ratings.insert((entry.player_id, scope.clone()), rating + change);
+                player_ids.push(entry.player_id);
+                game_ids.push(entry.game_id);
+                games.push(scope.clone());
+                new_ratings.push(rating + change);
+                changes.push(change);
+            }
+        }
+    }
+
+    sqlx::query("
+            INSERT INTO RatingHistory (player_id, game_id, game, rating, change)
+            SELECT * FROM UNNEST($1::int[], $2::int[], $3::text[], $4::float8[], $5::float8[])")
+        .bind(player_ids)
+        .bind(game_ids)
+        .bind(games)
+        .bind(new_ratings)
+        .bind(changes)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Rates the games of every group that has games but no ratings yet, which
+/// is everyone the first time the server starts with RatingHistory. After
+/// that the handlers that change games keep ratings up to date.
+pub async fn backfill(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let groups: Vec<(i32, DateTime<Utc>)> = sqlx::query_as("
+            SELECT group_id, MIN(date) FROM Games
+            WHERE group_id IS NOT NULL AND game IS NOT NULL AND NOT draft
+                AND group_id NOT IN (
+                    SELECT Games.group_id FROM RatingHistory JOIN Games ON Games.id = RatingHistory.game_id
+                )
+            GROUP BY group_id")
+        .fetch_all(pool)
+        .await?;
+
+    for (group_id, from) in &groups {
+        let mut tx = pool.begin().await?;
+        recompute_from(&mut tx, *group_id, *from).await?;
+        tx.commit().await?;
+    }
+
+    if !groups.is_empty() {
+        println!("Rated the games of {} groups", groups.len());
+    }
+
+    Ok(())
+}
+
+// The title ?game= picks, or None for global ratings
+fn rating_scope(game: Option<String>) -> Result<Option<String>, ApiError> {
+    scorings_for(game.as_deref())?;
+    Ok(game)
+}
+
+/// Everyone's current rating, globally or in one title, highest first
+#[axum::debug_handler]
+pub async fn ratings(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<RatingsQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+    let scope = rating_scope(params.game)?;
+
+    let ratings: Vec<RatingEntry> = sqlx::query_as("
+            WITH history AS (
+                SELECT r.player_id, r.rating, r.change, Games.date, Games.id AS game_id
+                FROM RatingHistory r
+                    JOIN Games ON Games.id = r.game_id
+                WHERE Games.group_id = $1 AND r.game IS NOT DISTINCT FROM $2
+            )
+            SELECT * FROM (
+                SELECT DISTINCT ON (history.player_id)
+                    history.player_id,
+                    Players.name AS player_name,
+                    history.rating,
+                    history.change AS last_change,
+                    COUNT(*) OVER (PARTITION BY history.player_id) AS games_played
+                FROM history
+                    JOIN Players ON Players.id = history.player_id
+                ORDER BY history.player_id, history.date DESC, history.game_id DESC
+            ) latest
+            ORDER BY rating DESC, player_name")
+        .bind(group_id)
+        .bind(&scope)
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(ratings))
+}
+
+/// How one player's rating changed game by game, globally or in one title
+#[axum::debug_handler]
+pub async fn rating_history(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(player_id): Path<i32>,
+    Query(params): Query<RatingsQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let scope = rating_scope(params.game)?;
+
+    let player: PlayerResponse = sqlx::query_as("
+            SELECT id, name FROM Players
+            WHERE id = $1 AND group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $2)")
+        .bind(player_id)
+        .bind(user.id())
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(String::from("Player not found")))?;
+
+    let history: Vec<RatingPoint> = sqlx::query_as("
+            SELECT Games.id AS game_id, Games.game, Games.date, r.rating, r.change
+            FROM RatingHistory r
+                JOIN Games ON Games.id = r.game_id
+            WHERE r.player_id = $1 AND r.game IS NOT DISTINCT FROM $2
+            ORDER BY Games.date, Games.id")
+        .bind(player.id)
+        .bind(&scope)
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(
+        RatingHistoryResponse {
+            player,
+            game: scope,
+            history
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn nothing_to_compare_against() {
+        assert!(rating_changes(&[]).is_empty());
+        assert_eq!(rating_changes(&[(1500.0, 1)]), vec![0.0]);
+    }
+
+    #[test]
+    fn even_one_on_one() {
+        let changes = rating_changes(&[(1500.0, 1), (1500.0, 2)]);
+        assert!(close(changes[0], K_FACTOR / 2.0));
+        assert!(close(changes[1], -K_FACTOR / 2.0));
+    }
+
+    #[test]
+    fn a_draw_between_equals_changes_nothing() {
+        let changes = rating_changes(&[(1500.0, 1), (1500.0, 1)]);
+        assert!(close(changes[0], 0.0));
+        assert!(close(changes[1], 0.0));
+    }
+
+    #[test]
+    fn tied_players_move_together() {
+        let changes = rating_changes(&[(1500.0, 1), (1500.0, 1), (1500.0, 3), (1500.0, 3)]);
+        assert!(close(changes[0], changes[1]));
+        assert!(close(changes[2], changes[3]));
+        assert!(changes[0] > 0.0);
+        assert!(close(changes[0], -changes[2]));
+    }
+
+    #[test]
+    fn four_player_game_splits_k() {
+        let changes = rating_changes(&[(1500.0, 1), (1500.0, 2), (1500.0, 3), (1500.0, 4)]);
+        assert!(close(changes[0], K_FACTOR / 2.0));
+        assert!(close(changes[3], -K_FACTOR / 2.0));
+        assert!(close(changes.iter().sum::<f64>(), 0.0));
+    }
+
+    #[test]
+    fn upsets_move_ratings_more() {
+        let expected = rating_changes(&[(1700.0, 1), (1300.0, 2)]);
+        let upset = rating_changes(&[(1700.0, 2), (1300.0, 1)]);
+        assert!(upset[1] > expected[0]);
+        assert!(upset[1] < K_FACTOR);
+        assert!(close(upset[0], -upset[1]));
+    }
+}
@@ -1,22 +1,44 @@
+use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
-use listfields_derive::ListFields;
+use serde_json::{Map, Value};
+use listfields_derive::{EntryTable, ListFields};
+use crate::entries::EntryTable;
 use crate::listfields::{ObjectData, EnumData, Variant, ListFields, Field};
 use chrono::{DateTime, Utc};
+use sqlx::{TypeInfo, postgres::{PgHasArrayType, PgTypeInfo}};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, ListFields, Debug)]
 pub struct GameData {
     #[serde(flatten)]
     pub player_data: MarioPartyData,
     pub board: String,
+    #[listfields(min = 1, max = 999)]
     pub turns: i32,
     pub date: DateTime<Utc>
 }
 
 // sqlx::Type is what lets us .bind() the enum to the sql query directly
 // instead of having to convert it to a string. This relies on a sql enum
-// existing for it though
-#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioPartyCharacters\"")] // Preserves casing
+// existing for it though.
+//
+// The type name has to be quoted to preserve casing when binding, but when
+// decoding sqlx compares it against the unquoted name postgres reports for
+// the column, so #[derive(sqlx::Type)] can't read these back. This accepts both.
+macro_rules! pg_enum_type {
+    ($ty:ident, $name:literal) => {
+        impl sqlx::Type<sqlx::Postgres> for $ty {
+            fn type_info() -> PgTypeInfo {
+                PgTypeInfo::with_name(concat!("\"", $name, "\""))
+            }
+
+            fn compatible(ty: &PgTypeInfo) -> bool {
+                *ty == Self::type_info() || ty.name() == $name
+            }
+        }
+    };
+}
+
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioPartyCharacters {
     Mario,
     Luigi,
@@ -28,8 +50,7 @@ pub enum MarioPartyCharacters {
     DonkeyKong
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty2Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty2Characters {
     Mario,
     Luigi,
@@ -41,8 +62,7 @@ pub enum MarioParty2Characters {
     DonkeyKong
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty3Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty3Characters {
     Mario,
     Luigi,
@@ -56,8 +76,7 @@ pub enum MarioParty3Characters {
     Waluigi
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty4Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty4Characters {
     Mario,
     Luigi,
@@ -71,8 +90,7 @@ pub enum MarioParty4Characters {
     Waluigi
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty5Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty5Characters {
     Mario,
     Luigi,
@@ -88,8 +106,7 @@ pub enum MarioParty5Characters {
     KoopaKid
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty6Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty6Characters {
     Mario,
     Luigi,
@@ -106,8 +123,7 @@ pub enum MarioParty6Characters {
     Toadette
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty7Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty7Characters {
     Mario,
     Luigi,
@@ -125,8 +141,7 @@ pub enum MarioParty7Characters {
     DryBones
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty8Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty8Characters {
     Mario,
     Luigi,
@@ -148,8 +163,7 @@ pub enum MarioParty8Characters {
     HammerBro
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty9Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty9Characters {
     Mario,
     Luigi,
@@ -167,8 +181,7 @@ pub enum MarioParty9Characters {
     Magikoopa
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty10Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioParty10Characters {
     Mario,
     Luigi,
@@ -187,8 +200,7 @@ pub enum MarioParty10Characters {
     Spike
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioPartyDSCharacters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioPartyDSCharacters {
     Mario,
     Luigi,
@@ -200,8 +212,7 @@ pub enum MarioPartyDSCharacters {
     Toad
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty:IslandTourCharacters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioPartyIslandTourCharacters {
     Mario,
     Luigi,
@@ -217,8 +228,7 @@ pub enum MarioPartyIslandTourCharacters {
     BowserJr
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty:StarRushCharacters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioPartyStarRushCharacters {
     Mario,
     Luigi,
@@ -238,8 +248,7 @@ pub enum MarioPartyStarRushCharacters {
     DiddyKong
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioParty:TheTop100Characters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioPartyTop100Characters {
     Mario,
     Luigi,
@@ -251,8 +260,7 @@ pub enum MarioPartyTop100Characters {
     Rosalina
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"SuperMarioPartyCharacters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum SuperMarioPartyCharacters {
     Mario,
     Luigi,
@@ -292,8 +300,7 @@ pub enum SuperMarioPartyCharacters {
     PomPom
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioPartySuperstarsCharacters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioPartySuperstarsCharacters {
     Mario,
     Luigi,
@@ -309,8 +316,7 @@ pub enum MarioPartySuperstarsCharacters {
     Birdo
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "\"MarioPartyJamboreeCharacters\"")] // Preserves casing
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, sqlx::Encode, sqlx::Decode)]
 pub enum MarioPartyJamboreeCharacters {
     Mario,
     Luigi,
@@ -344,330 +350,1273 @@ pub enum MarioPartyJamboreeCharacters {
     Ninji
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+pg_enum_type!(MarioPartyCharacters, "MarioPartyCharacters");
+pg_enum_type!(MarioParty2Characters, "MarioParty2Characters");
+pg_enum_type!(MarioParty3Characters, "MarioParty3Characters");
+pg_enum_type!(MarioParty4Characters, "MarioParty4Characters");
+pg_enum_type!(MarioParty5Characters, "MarioParty5Characters");
+pg_enum_type!(MarioParty6Characters, "MarioParty6Characters");
+pg_enum_type!(MarioParty7Characters, "MarioParty7Characters");
+pg_enum_type!(MarioParty8Characters, "MarioParty8Characters");
+pg_enum_type!(MarioParty9Characters, "MarioParty9Characters");
+pg_enum_type!(MarioParty10Characters, "MarioParty10Characters");
+pg_enum_type!(MarioPartyDSCharacters, "MarioPartyDSCharacters");
+pg_enum_type!(MarioPartyIslandTourCharacters, "MarioParty:IslandTourCharacters");
+pg_enum_type!(MarioPartyStarRushCharacters, "MarioParty:StarRushCharacters");
+pg_enum_type!(MarioPartyTop100Characters, "MarioParty:TheTop100Characters");
+pg_enum_type!(SuperMarioPartyCharacters, "SuperMarioPartyCharacters");
+pg_enum_type!(MarioPartySuperstarsCharacters, "MarioPartySuperstarsCharacters");
+pg_enum_type!(MarioPartyJamboreeCharacters, "MarioPartyJamboreeCharacters");
+
+// Bonus stars from every game. Which ones show up depends on the game, and
+// in the newer games on the board and some randomness, so they aren't
+// checked against the game
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug, PartialEq, sqlx::Encode, sqlx::Decode)]
+pub enum BonusStar {
+    #[serde(rename = "Minigame Star")]
+    #[sqlx(rename = "Minigame Star")]
+    MinigameStar,
+    #[serde(rename = "Coin Star")]
+    #[sqlx(rename = "Coin Star")]
+    CoinStar,
+    #[serde(rename = "Happening Star")]
+    #[sqlx(rename = "Happening Star")]
+    HappeningStar,
+    #[serde(rename = "Orb Star")]
+    #[sqlx(rename = "Orb Star")]
+    OrbStar,
+    #[serde(rename = "Running Star")]
+    #[sqlx(rename = "Running Star")]
+    RunningStar,
+    #[serde(rename = "Candy Star")]
+    #[sqlx(rename = "Candy Star")]
+    CandyStar,
+    #[serde(rename = "Shopping Star")]
+    #[sqlx(rename = "Shopping Star")]
+    ShoppingStar,
+    #[serde(rename = "Item Star")]
+    #[sqlx(rename = "Item Star")]
+    ItemStar,
+    #[serde(rename = "Ally Star")]
+    #[sqlx(rename = "Ally Star")]
+    AllyStar,
+    #[serde(rename = "Slowpoke Star")]
+    #[sqlx(rename = "Slowpoke Star")]
+    SlowpokeStar,
+    #[serde(rename = "Sightseer Star")]
+    #[sqlx(rename = "Sightseer Star")]
+    SightseerStar,
+    #[serde(rename = "Eventful Star")]
+    #[sqlx(rename = "Eventful Star")]
+    EventfulStar,
+    #[serde(rename = "Unlucky Star")]
+    #[sqlx(rename = "Unlucky Star")]
+    UnluckyStar
+}
+
+pg_enum_type!(BonusStar, "BonusStar");
+
+// Lets us bind and read Vec<BonusStar> for "BonusStar"[] columns. Same
+// quoting problem as pg_enum_type!
+impl PgHasArrayType for BonusStar {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("\"_BonusStar\"")
+    }
+
+    fn array_compatible(ty: &PgTypeInfo) -> bool {
+        *ty == Self::array_type_info() || ty.name() == "_BonusStar"
+    }
+}
+
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioPartyCharacters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub peak_coins: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub question_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub minigame_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub exclaimation_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub mushroom_spaces: i32,
-    pub bowser_spaces: i32
+    #[listfields(min = 0, per_turn)]
+    pub bowser_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty2 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty2Characters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub peak_coins: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub question_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub exclaimation_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub battle_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub item_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bank_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty3 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty3Characters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub peak_coins: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub question_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub exclaimation_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub battle_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub item_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bank_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub game_guy_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty4 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty4Characters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub peak_coins: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub happening_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub fortune_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub battle_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub mushroom_spaces: i32,
-    pub warp_spaces: i32
+    #[listfields(min = 0, per_turn)]
+    pub warp_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty5 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty5Characters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub peak_coins: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub capsule_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub question_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
-    pub dk_spaces: i32
+    #[listfields(min = 0, per_turn)]
+    pub dk_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty6 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty6Characters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0)]
     pub orbs_used: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub character_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub question_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub duel_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub miracle_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
-    pub dk_spaces: i32
+    #[listfields(min = 0, per_turn)]
+    pub dk_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty7 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty7Characters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0)]
     pub coins_spent_on_orbs: i32,
+    #[listfields(min = 0)]
     pub orbs_used: i32,
+    #[listfields(min = 0)]
     pub spaces_moved: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub character_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub green_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub duel_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub mic_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub dk_spaces: i32,
-    pub bowser_spaces: i32
+    #[listfields(min = 0, per_turn)]
+    pub bowser_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty8 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty8Characters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0)]
     pub coins_spent_in_shop: i32,
+    #[listfields(min = 0)]
     pub candy_eaten: i32,
+    #[listfields(min = 0)]
     pub spaces_moved: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub green_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub lucky_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub dk_spaces: i32,
-    pub bowser_spaces: i32
+    #[listfields(min = 0, per_turn)]
+    pub bowser_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty9 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty9Characters,
+    #[listfields(min = 0, max = 999)]
     pub mini_stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub mini_stars_lost: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_stars_lost: i32,
+    #[listfields(min = 0, max = 999)]
     pub event_mini_stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub event_mini_stars_lost: i32,
+    #[listfields(min = 0, max = 999)]
     pub bonus_star_mini_stars: i32,
+    #[listfields(min = 0, per_turn)]
     pub dice_block_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub lucky_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub unlucky_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub spin_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub event_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub shuffle_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub captain_event_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub boss_battle_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub dash_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub back_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub free_for_all_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub one_v_three_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub battle_spaces: i32,
-    pub bowser_jr_spaces: i32
+    #[listfields(min = 0, per_turn)]
+    pub bowser_jr_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioParty10 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioParty10Characters,
-    pub mini_stars: i32
+    #[listfields(min = 0, max = 999)]
+    pub mini_stars: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioPartyDS {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioPartyDSCharacters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
-    pub coins: i32
+    #[listfields(min = 0, max = 999)]
+    pub coins: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioPartyIslandTour {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioPartyIslandTourCharacters,
+    #[listfields(min = 0, per_turn)]
     pub green_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub item_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub dash_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_event_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub piranha_plant_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_event_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub free_for_all_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
-    pub spaces_moved: i32
+    #[listfields(min = 0)]
+    pub spaces_moved: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioPartyStarRush {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioPartyStarRushCharacters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
-    pub coins: i32
+    #[listfields(min = 0, max = 999)]
+    pub coins: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioPartyTop100 {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioPartyTop100Characters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
-    pub coins: i32
+    #[listfields(min = 0, max = 999)]
+    pub coins: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct SuperMarioParty {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: SuperMarioPartyCharacters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0)]
     pub hidden_blocks: i32,
+    #[listfields(min = 0)]
     pub items_used: i32,
+    #[listfields(min = 0)]
     pub allies: i32,
+    #[listfields(min = 0)]
     pub spaces_moved: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub lucky_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub vs_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub event_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub item_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub ally_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bad_luck_spaces: i32,
-    pub extra_bad_luck_spaces: i32
+    #[listfields(min = 0, per_turn)]
+    pub extra_bad_luck_spaces: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioPartySuperstars {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioPartySuperstarsCharacters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, max = 999)]
     pub minigame_coins: i32,
+    #[listfields(min = 0)]
     pub hidden_blocks: i32,
+    #[listfields(min = 0)]
     pub items_bought: i32,
+    #[listfields(min = 0)]
     pub items_used: i32,
+    #[listfields(min = 0)]
     pub spaces_moved: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub item_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub event_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub chance_time_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub lucky_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub vs_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub koopa_bank_spaces: i32,
-    pub stickers_used: i32
+    #[listfields(min = 0)]
+    pub stickers_used: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, EntryTable, Clone, Debug, sqlx::FromRow)]
 pub struct MarioPartyJamboree {
-    pub player_name: String,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
     pub character: MarioPartyJamboreeCharacters,
+    #[listfields(min = 0, max = 999)]
     pub stars: i32,
+    #[listfields(min = 0, max = 999)]
     pub coins: i32,
+    #[listfields(min = 0, per_turn)]
     pub blue_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub red_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub lucky_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub unlucky_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub item_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub bowser_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub event_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub chance_time_spaces: i32,
+    #[listfields(min = 0, per_turn)]
     pub vs_spaces: i32,
+    #[listfields(min = 0)]
     pub coins_received: i32,
+    #[listfields(min = 0)]
     pub minigames_won: i32,
+    #[listfields(min = 0)]
     pub hidden_blocks_found: i32,
+    #[listfields(min = 0)]
     pub showdown_minigames_won: i32,
+    #[listfields(min = 0)]
     pub items_bought: i32,
+    #[listfields(min = 0)]
     pub items_used: i32,
+    #[listfields(min = 0)]
     pub spaces_traveled: i32,
+    #[listfields(min = 0)]
     pub reactions_used: i32,
+    #[listfields(min = 1, max = 4)]
+    pub placement: i32,
+    pub bonus_stars: Vec<BonusStar>
 }
 
-#[derive(Deserialize, ListFields, Clone, Debug)]
+#[derive(Serialize, Deserialize, ListFields, Clone, Debug)]
 #[serde(tag = "game", content = "player_data")]
 pub enum MarioPartyData {
     #[serde(rename = "Mario Party")]
+    #[listfields(min = 1, max = 4)]
     MarioParty(Vec<MarioParty>),
     #[serde(rename = "Mario Party 2")]
+    #[listfields(min = 1, max = 4)]
     MarioParty2(Vec<MarioParty2>),
     #[serde(rename = "Mario Party 3")]
+    #[listfields(min = 1, max = 4)]
     MarioParty3(Vec<MarioParty3>),
     #[serde(rename = "Mario Party 4")]
+    #[listfields(min = 1, max = 4)]
     MarioParty4(Vec<MarioParty4>),
     #[serde(rename = "Mario Party 5")]
+    #[listfields(min = 1, max = 4)]
     MarioParty5(Vec<MarioParty5>),
     #[serde(rename = "Mario Party 6")]
+    #[listfields(min = 1, max = 4)]
     MarioParty6(Vec<MarioParty6>),
     #[serde(rename = "Mario Party 7")]
+    #[listfields(min = 1, max = 4)]
     MarioParty7(Vec<MarioParty7>),
     #[serde(rename = "Mario Party 8")]
+    #[listfields(min = 1, max = 4)]
     MarioParty8(Vec<MarioParty8>),
     #[serde(rename = "Mario Party 9")]
+    #[listfields(min = 1, max = 4)]
     MarioParty9(Vec<MarioParty9>),
     #[serde(rename = "Mario Party 10")]
+    #[listfields(min = 1, max = 4)]
     MarioParty10(Vec<MarioParty10>),
     #[serde(rename = "Mario Party DS")]
+    #[listfields(min = 1, max = 4)]
     MarioPartyDS(Vec<MarioPartyDS>),
     #[serde(rename = "Mario Party: Island Tour")]
+    #[listfields(min = 1, max = 4)]
     MarioPartyIslandTour(Vec<MarioPartyIslandTour>),
     #[serde(rename = "Mario Party: Star Rush")]
+    #[listfields(min = 1, max = 4)]
     MarioPartyStarRush(Vec<MarioPartyStarRush>),
     #[serde(rename = "Mario Party: The Top 100")]
+    #[listfields(min = 1, max = 4)]
     MarioPartyTop100(Vec<MarioPartyTop100>),
     #[serde(rename = "Super Mario Party")]
+    #[listfields(min = 1, max = 4)]
     SuperMarioParty(Vec<SuperMarioParty>),
     #[serde(rename = "Mario Party Superstars")]
+    #[listfields(min = 1, max = 4)]
     MarioPartySuperstars(Vec<MarioPartySuperstars>),
     #[serde(rename = "Mario Party Jamboree")]
+    #[listfields(min = 1, max = 4)]
     MarioPartyJamboree(Vec<MarioPartyJamboree>)
 }
+
+impl MarioPartyData {
+    /// The name of the game, the same as the serde tag and the Games.game column
+    pub fn game_name(&self) -> &'static str {
+        match self {
+            MarioPartyData::MarioParty(_) => "Mario Party",
+            MarioPartyData::MarioParty2(_) => "Mario Party 2",
+            MarioPartyData::MarioParty3(_) => "Mario Party 3",
+            MarioPartyData::MarioParty4(_) => "Mario Party 4",
+            MarioPartyData::MarioParty5(_) => "Mario Party 5",
+            MarioPartyData::MarioParty6(_) => "Mario Party 6",
+            MarioPartyData::MarioParty7(_) => "Mario Party 7",
+            MarioPartyData::MarioParty8(_) => "Mario Party 8",
+            MarioPartyData::MarioParty9(_) => "Mario Party 9",
+            MarioPartyData::MarioParty10(_) => "Mario Party 10",
+            MarioPartyData::MarioPartyDS(_) => "Mario Party DS",
+            MarioPartyData::MarioPartyIslandTour(_) => "Mario Party: Island Tour",
+            MarioPartyData::MarioPartyStarRush(_) => "Mario Party: Star Rush",
+            MarioPartyData::MarioPartyTop100(_) => "Mario Party: The Top 100",
+            MarioPartyData::SuperMarioParty(_) => "Super Mario Party",
+            MarioPartyData::MarioPartySuperstars(_) => "Mario Party Superstars",
+            MarioPartyData::MarioPartyJamboree(_) => "Mario Party Jamboree"
+        }
+    }
+
+    /// The player_id and player_name of every player in the game, for filling
+    /// in whichever one the client left out
+    pub fn players_mut(&mut self) -> Vec<(&mut Option<i32>, &mut Option<String>)> {
+        match self {
+            MarioPartyData::MarioParty(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty2(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty3(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty4(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty5(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty6(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty7(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty8(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty9(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioParty10(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioPartyDS(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioPartyIslandTour(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioPartyStarRush(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioPartyTop100(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::SuperMarioParty(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioPartySuperstars(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect(),
+            MarioPartyData::MarioPartyJamboree(players_data) => players_data.iter_mut().map(|player_data| (&mut player_data.player_id, &mut player_data.player_name)).collect()
+        }
+    }
+
+    fn placements(&self) -> Vec<i32> {
+        match self {
+            MarioPartyData::MarioParty(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty2(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty3(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty4(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty5(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty6(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty7(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty8(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty9(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioParty10(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioPartyDS(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioPartyIslandTour(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioPartyStarRush(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioPartyTop100(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::SuperMarioParty(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioPartySuperstars(players_data) => players_data.iter().map(|player_data| player_data.placement).collect(),
+            MarioPartyData::MarioPartyJamboree(players_data) => players_data.iter().map(|player_data| player_data.placement).collect()
+        }
+    }
+
+    /// Checks that the players' placements are 1 through the number of
+    /// players, with each used exactly once
+    pub fn check_placements(&self) -> Result<(), String> {
+        let mut placements = self.placements();
+        placements.sort_unstable();
+
+        if placements.iter().copied().eq(1..=placements.len() as i32) {
+            Ok(())
+        }
+        else {
+            Err(format!("Placements must be 1 through {} with no repeats, got {:?}", placements.len(), placements))
+        }
+    }
+
+    /// The *Entries table that stores the player data for the game called `game`
+    pub fn entries_table(game: &str) -> Option<&'static str> {
+        match game {
+            "Mario Party" => Some(MarioParty::TABLE),
+            "Mario Party 2" => Some(MarioParty2::TABLE),
+            "Mario Party 3" => Some(MarioParty3::TABLE),
+            "Mario Party 4" => Some(MarioParty4::TABLE),
+            "Mario Party 5" => Some(MarioParty5::TABLE),
+            "Mario Party 6" => Some(MarioParty6::TABLE),
+            "Mario Party 7" => Some(MarioParty7::TABLE),
+            "Mario Party 8" => Some(MarioParty8::TABLE),
+            "Mario Party 9" => Some(MarioParty9::TABLE),
+            "Mario Party 10" => Some(MarioParty10::TABLE),
+            "Mario Party DS" => Some(MarioPartyDS::TABLE),
+            "Mario Party: Island Tour" => Some(MarioPartyIslandTour::TABLE),
+            "Mario Party: Star Rush" => Some(MarioPartyStarRush::TABLE),
+            "Mario Party: The Top 100" => Some(MarioPartyTop100::TABLE),
+            "Super Mario Party" => Some(SuperMarioParty::TABLE),
+            "Mario Party Superstars" => Some(MarioPartySuperstars::TABLE),
+            "Mario Party Jamboree" => Some(MarioPartyJamboree::TABLE),
+            _ => None
+        }
+    }
+}
+
+/// Every title's name and the fields of its player data, from ListFields
+pub fn titles() -> Vec<(String, Vec<Field>)> {
+    let mut titles = Vec::new();
+    if let ObjectData::EnumData(EnumData { name: _, variants }) = MarioPartyData::list_fields() {
+        for variant in variants {
+            if let Some(ObjectData::Fields(fields)) = variant.type_data {
+                titles.push((variant.name, fields));
+            }
+        }
+    }
+
+    titles
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc
+}
+
+// Query parameters for GET /api/games
+#[derive(Deserialize, Debug)]
+pub struct GamesQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    #[serde(default)]
+    pub sort: SortOrder
+}
+
+// Query parameters for GET /api/stats/leaderboard
+#[derive(Deserialize, Debug)]
+pub struct LeaderboardQuery {
+    pub game: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>
+}
+
+// Query parameters for GET /api/stats/characters
+#[derive(Deserialize, Debug)]
+pub struct CharacterStatsQuery {
+    pub game: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>
+}
+
+// Query parameters for GET /api/stats/h2h. a and b are each a player's id
+// or name
+#[derive(Deserialize, Debug)]
+pub struct HeadToHeadQuery {
+    pub a: Option<String>,
+    pub b: Option<String>,
+    pub game: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>
+}
+
+// Query parameters for GET /api/ratings and GET /api/ratings/:player_id.
+// Without a game the ratings are the global ones
+#[derive(Deserialize, Debug)]
+pub struct RatingsQuery {
+    pub game: Option<String>
+}
+
+// Query parameters for GET /api/stats/spaces. Without a game every game
+// that's been played is included
+#[derive(Deserialize, Debug)]
+pub struct SpaceStatsQuery {
+    pub game: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>
+}
+
+// One player at the end of one turn, in the body of PUT /api/games/:id/turns.
+// The player is one of the game's, by id or by the name they have in the game
+#[derive(Deserialize, Debug)]
+pub struct TurnSnapshot {
+    pub turn: i32,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
+    pub stars: Option<i32>,
+    pub coins: Option<i32>,
+    // The *_spaces field of the space they landed on
+    pub space: Option<String>
+}
+
+// How a minigame was played, stored in MinigameResults.minigame_type
+#[derive(Deserialize, PartialEq, Debug)]
+pub enum MinigameType {
+    #[serde(rename = "4P")]
+    FourPlayer,
+    #[serde(rename = "1v3")]
+    OneVsThree,
+    #[serde(rename = "2v2")]
+    TwoVsTwo,
+    #[serde(rename = "duel")]
+    Duel,
+    #[serde(rename = "battle")]
+    Battle
+}
+
+impl MinigameType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MinigameType::FourPlayer => "4P",
+            MinigameType::OneVsThree => "1v3",
+            MinigameType::TwoVsTwo => "2v2",
+            MinigameType::Duel => "duel",
+            MinigameType::Battle => "battle"
+        }
+    }
+}
+
+// One of a game's players, by id or by name
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum PlayerRef {
+    Id(i32),
+    Name(String)
+}
+
+impl std::fmt::Display for PlayerRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerRef::Id(id) => write!(f, "Player {id}"),
+            PlayerRef::Name(name) => write!(f, "{name}")
+        }
+    }
+}
+
+// One minigame in the body of PUT /api/games/:id/minigames. Without
+// players, everyone in the game played it. A 1v3 minigame says which player
+// was on their own with solo.
+#[derive(Deserialize, Debug)]
+pub struct MinigameResultData {
+    pub turn: Option<i32>,
+    pub minigame: String,
+    #[serde(rename = "type")]
+    pub minigame_type: MinigameType,
+    pub players: Option<Vec<PlayerRef>>,
+    #[serde(default)]
+    pub winners: Vec<PlayerRef>,
+    pub solo: Option<PlayerRef>
+}
+
+// Query parameters for GET /api/minigames
+#[derive(Deserialize, Debug)]
+pub struct MinigameCatalogQuery {
+    pub game: Option<String>
+}
+
+// Query parameters for GET /api/stats/minigames
+#[derive(Deserialize, Debug)]
+pub struct MinigameStatsQuery {
+    pub game: Option<String>,
+    pub minigame: Option<String>
+}
+
+// Query parameters for GET /api/export.csv
+#[derive(Deserialize, Debug)]
+pub struct ExportQuery {
+    pub game: Option<String>
+}
+
+// Body for POST /api/players and PUT /api/players/:id
+#[derive(Deserialize, Debug)]
+pub struct PlayerData {
+    pub name: String
+}
+
+// Which group an endpoint reads from or adds to, as ?group= on any of them.
+// Without it, it's the user's personal group
+#[derive(Deserialize, Debug)]
+pub struct GroupQuery {
+    pub group: Option<i32>
+}
+
+// Body for POST /api/groups and PUT /api/groups/:id
+#[derive(Deserialize, Debug)]
+pub struct GroupData {
+    pub name: String
+}
+
+// Body for POST /api/groups/:id/invitations
+#[derive(Deserialize, Debug)]
+pub struct InvitationData {
+    pub username: String
+}
+
+// Stored in GroupMembers.role
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupRole {
+    Owner,
+    Member
+}
+
+impl GroupRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupRole::Owner => "owner",
+            GroupRole::Member => "member"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GroupRole> {
+        match name {
+            "owner" => Some(GroupRole::Owner),
+            "member" => Some(GroupRole::Member),
+            _ => None
+        }
+    }
+}
+
+// Body for PUT /api/groups/:id/members/:user_id
+#[derive(Deserialize, Debug)]
+pub struct MemberRole {
+    pub role: GroupRole
+}
+
+// Stored in users.role. Read-only users can look at their groups but not
+// change anything, admins can also manage other users
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    Admin,
+    User,
+    ReadOnly
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::User => "user",
+            Role::ReadOnly => "read-only"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Role> {
+        match name {
+            "admin" => Some(Role::Admin),
+            "user" => Some(Role::User),
+            "read-only" => Some(Role::ReadOnly),
+            _ => None
+        }
+    }
+}
+
+// So sqlx can read users.role straight into a Role
+impl TryFrom<String> for Role {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Role, String> {
+        Role::from_name(&name).ok_or_else(|| format!("{name} isn't a role"))
+    }
+}
+
+// Body for PUT /api/admin/users/:id, anything left out stays how it is
+#[derive(Deserialize, Debug)]
+pub struct UserUpdate {
+    pub role: Option<Role>,
+    pub disabled: Option<bool>
+}
+
+// Form for POST /api/me/password
+#[derive(Deserialize)]
+pub struct PasswordChange {
+    pub current_password: String,
+    pub new_password: String
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    Jsonl
+}
+
+// Query parameters for POST /api/import
+#[derive(Deserialize, Debug)]
+pub struct ImportQuery {
+    // Worked out from the Content-Type if it isn't given
+    pub format: Option<ImportFormat>,
+    #[serde(default)]
+    pub dry_run: bool
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreMode {
+    // Keep what's already there, adding what isn't
+    #[default]
+    Merge,
+    // Delete every game and player first
+    Replace
+}
+
+// Query parameters for POST /api/me/restore
+#[derive(Deserialize, Debug)]
+pub struct RestoreQuery {
+    #[serde(default)]
+    pub mode: RestoreMode
+}
+
+// A player in a backup. The id is only used to match up the player_ids in
+// the backup's games, it's never kept
+#[derive(Deserialize, Debug)]
+pub struct BackupPlayer {
+    pub id: i32,
+    pub name: String
+}
+
+// A game in a backup, shaped like responses::GameResponse
+#[derive(Deserialize, Debug)]
+pub struct BackupGame {
+    pub id: i32,
+    #[serde(flatten)]
+    pub player_data: MarioPartyData,
+    pub board: Option<String>,
+    pub turns: i32,
+    pub date: DateTime<Utc>,
+    // Version 1 backups don't have these
+    #[serde(default)]
+    pub timeline: Vec<TurnSnapshot>,
+    #[serde(default)]
+    pub minigames: Vec<BackupMinigame>
+}
+
+// A minigame in a backup, shaped like responses::MinigameResultResponse
+#[derive(Deserialize, Debug)]
+pub struct BackupMinigame {
+    pub turn: Option<i32>,
+    pub minigame: String,
+    #[serde(rename = "type")]
+    pub minigame_type: MinigameType,
+    pub players: Vec<BackupPlayer>,
+    pub winners: Vec<BackupPlayer>,
+    #[serde(default)]
+    pub solo: Option<BackupPlayer>
+}
+
+// Body for POST /api/me/restore, what GET /api/me/backup gives
+#[derive(Deserialize, Debug)]
+pub struct BackupData {
+    pub version: u32,
+    pub players: Vec<BackupPlayer>,
+    pub games: Vec<BackupGame>
+}
+
+// Body for POST /api/live. Each player is the start of their entry, like
+// {"player_name": "Jon", "character": "Mario"}, and any number that isn't
+// given starts at 0
+#[derive(Deserialize, Debug)]
+pub struct LiveGameStart {
+    pub game: String,
+    pub board: String,
+    pub turns: i32,
+    pub date: Option<DateTime<Utc>>,
+    pub players: Vec<Map<String, Value>>
+}
+
+// A message sent over a live game's websocket. `player` is an index into
+// the game's player_data
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LiveCommand {
+    // Adds to some of a player's numbers, like {"stars": 1, "coins": -10}
+    Delta {
+        player: usize,
+        changes: HashMap<String, i64>
+    },
+    // Sets one of a player's fields, or one of the game's if there's no player
+    Set {
+        player: Option<usize>,
+        field: String,
+        value: Value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(placement: i32) -> MarioParty2 {
+        MarioParty2 {
+            player_id: None,
+            player_name: Some(format!("Player {}", placement)),
+            character: MarioParty2Characters::Mario,
+            stars: 0,
+            coins: 0,
+            minigame_coins: 0,
+            peak_coins: 0,
+            blue_spaces: 0,
+            red_spaces: 0,
+            question_spaces: 0,
+            exclaimation_spaces: 0,
+            bowser_spaces: 0,
+            battle_spaces: 0,
+            item_spaces: 0,
+            bank_spaces: 0,
+            placement,
+            bonus_stars: vec![]
+        }
+    }
+
+    fn game(placements: &[i32]) -> MarioPartyData {
+        MarioPartyData::MarioParty2(placements.iter().copied().map(player).collect())
+    }
+
+    #[test]
+    fn placements_in_any_order() {
+        assert!(game(&[1, 2, 3, 4]).check_placements().is_ok());
+        assert!(game(&[3, 1, 4, 2]).check_placements().is_ok());
+        assert!(game(&[2, 1]).check_placements().is_ok());
+        assert!(game(&[1]).check_placements().is_ok());
+    }
+
+    #[test]
+    fn placements_with_repeats() {
+        assert_eq!(
+            game(&[1, 1, 3, 4]).check_placements(),
+            Err("Placements must be 1 through 4 with no repeats, got [1, 1, 3, 4]".to_string())
+        );
+    }
+
+    #[test]
+    fn placements_past_the_number_of_players() {
+        assert!(game(&[1, 2, 4]).check_placements().is_err());
+        assert!(game(&[2]).check_placements().is_err());
+        assert!(game(&[0, 1, 2]).check_placements().is_err());
+    }
+
+    #[test]
+    fn validate_within_limits() {
+        let mut data = player(1);
+        data.stars = 999;
+        data.blue_spaces = 20;
+        assert!(data.validate(20).is_ok());
+    }
+
+    #[test]
+    fn validate_min_and_max() {
+        let mut data = player(1);
+        data.coins = -1;
+        assert_eq!(data.validate(20), Err("coins can't be less than 0, got -1".to_string()));
+
+        let mut data = player(1);
+        data.stars = 1000;
+        assert_eq!(data.validate(20), Err("stars can't be more than 999, got 1000".to_string()));
+    }
+
+    #[test]
+    fn validate_per_turn() {
+        let mut data = player(1);
+        data.red_spaces = 21;
+        assert_eq!(
+            data.validate(20),
+            Err("red_spaces can't be more than the number of turns (20), got 21".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_number_of_players() {
+        assert_eq!(
+            game(&[]).validate(20),
+            Err("Mario Party 2 can't have fewer than 1 entries, got 0".to_string())
+        );
+        assert_eq!(
+            game(&[1, 2, 3, 4, 5]).validate(20),
+            Err("Mario Party 2 can't have more than 4 entries, got 5".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_names_the_player() {
+        let mut data = game(&[1, 2]);
+        if let MarioPartyData::MarioParty2(players) = &mut data {
+            players[1].placement = 5;
+        }
+        assert_eq!(data.validate(20), Err("Mario Party 2 #2: placement can't be more than 4, got 5".to_string()));
+    }
+}
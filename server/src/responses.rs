@@ -1,7 +1,364 @@
+use std::collections::BTreeMap;
 use serde::Serialize;
+use serde_json::Value;
+use chrono::{DateTime, Utc};
+use crate::requests::{MarioPartyData, Role};
 
 #[derive(Serialize)]
 pub struct MessageResponse {
     pub message: String,
     pub success: bool
 }
+
+#[derive(Serialize, Debug)]
+pub struct UserResponse {
+    pub id: i32,
+    pub username: String,
+    pub role: Role
+}
+
+// The same shape as requests::GameData, plus the id, so a game can be
+// read back and submitted again
+#[derive(Serialize, Debug)]
+pub struct GameResponse {
+    pub id: i32,
+    #[serde(flatten)]
+    pub player_data: MarioPartyData,
+    pub board: Option<String>,
+    pub turns: i32,
+    pub date: DateTime<Utc>
+}
+
+#[derive(Serialize, Debug)]
+pub struct GamesResponse {
+    pub games: Vec<GameResponse>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64
+}
+
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct PlayerResponse {
+    pub id: i32,
+    pub name: String
+}
+
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct LeaderboardEntry {
+    pub player_id: i32,
+    pub player_name: String,
+    pub games_played: i64,
+    pub wins: i64,
+    pub average_placement: f64,
+    // Null if every game the player was in doesn't track stars
+    pub average_stars: Option<f64>
+}
+
+#[derive(Serialize, Debug)]
+pub struct CharacterStats {
+    pub character: String,
+    pub picks: i64,
+    // The fraction of games the character was in
+    pub pick_rate: f64,
+    pub wins: i64,
+    // The fraction of the character's games they won
+    pub win_rate: f64,
+    // Null if the game doesn't track stars or coins
+    pub average_stars: Option<f64>,
+    pub average_coins: Option<f64>
+}
+
+#[derive(Serialize, Debug)]
+pub struct CharacterStatsResponse {
+    pub game: String,
+    pub games_played: i64,
+    pub characters: Vec<CharacterStats>
+}
+
+// One game two players were both in, from a's side
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct HeadToHeadGame {
+    pub game_id: i32,
+    pub game: String,
+    pub date: DateTime<Utc>,
+    pub a_placement: i32,
+    pub b_placement: i32,
+    pub a_stars: Option<f64>,
+    pub b_stars: Option<f64>
+}
+
+#[derive(Serialize, Debug)]
+pub struct HeadToHead {
+    pub a: PlayerResponse,
+    pub b: PlayerResponse,
+    // Games a placed ahead of b in, behind b in, and level with b in
+    pub wins: i64,
+    pub losses: i64,
+    pub ties: i64,
+    // a's stars minus b's, null if none of the games track stars
+    pub average_star_differential: Option<f64>,
+    // Newest first
+    pub games: Vec<HeadToHeadGame>
+}
+
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct RatingEntry {
+    pub player_id: i32,
+    pub player_name: String,
+    pub rating: f64,
+    // How much the player's last game moved their rating
+    pub last_change: f64,
+    pub games_played: i64
+}
+
+// A player's rating after one game
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct RatingPoint {
+    pub game_id: i32,
+    pub game: String,
+    pub date: DateTime<Utc>,
+    pub rating: f64,
+    pub change: f64
+}
+
+#[derive(Serialize, Debug)]
+pub struct RatingHistoryResponse {
+    pub player: PlayerResponse,
+    // The title the ratings are for, null for global ratings
+    pub game: Option<String>,
+    // Oldest first
+    pub history: Vec<RatingPoint>
+}
+
+#[derive(Serialize, Debug)]
+pub struct PlayerSpaces {
+    pub player_id: i32,
+    pub player_name: String,
+    pub games_played: i64,
+    pub turns_played: i64,
+    // Landings on each kind of space per turn played
+    pub spaces: BTreeMap<String, f64>
+}
+
+#[derive(Serialize, Debug)]
+pub struct SpaceStats {
+    pub game: String,
+    pub games_played: i64,
+    pub players: Vec<PlayerSpaces>,
+    // How each kind of space's landings per turn go with placement, from -1
+    // to 1. Negative means landing there more goes with placing better.
+    // Null when there isn't enough to tell
+    pub correlations: BTreeMap<String, Option<f64>>
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PlayerTurn {
+    pub player_id: i32,
+    pub player_name: String,
+    pub stars: Option<i32>,
+    pub coins: Option<i32>,
+    pub space: Option<String>
+}
+
+#[derive(Serialize, Debug)]
+pub struct TurnResponse {
+    pub turn: i32,
+    pub players: Vec<PlayerTurn>
+}
+
+#[derive(Serialize, Debug)]
+pub struct TimelineResponse {
+    pub game_id: i32,
+    pub game: String,
+    pub turns: Vec<TurnResponse>,
+    // Whether the last snapshot of every player matches their entry
+    pub consistent: bool,
+    // What doesn't match, if anything
+    pub problems: Vec<String>
+}
+
+// How one game in an import went
+#[derive(Serialize, Debug)]
+pub struct ImportRecord {
+    // The line of the file the game starts on
+    pub line: usize,
+    pub game: Option<String>,
+    pub accepted: bool,
+    // Only set when the import wasn't a dry run
+    pub game_id: Option<i32>,
+    // Why the game was rejected
+    pub reason: Option<String>
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub accepted: usize,
+    pub rejected: usize,
+    pub records: Vec<ImportRecord>
+}
+
+// One group's players and games, from GET /api/me/backup
+#[derive(Serialize, Debug)]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub username: String,
+    // The group this is a backup of, a backup only ever has one
+    pub group_id: i32,
+    pub group: String,
+    pub players: Vec<PlayerResponse>,
+    pub games: Vec<BackupGameResponse>
+}
+
+// A game in a backup, with anything recorded about it besides its entries
+#[derive(Serialize, Debug)]
+pub struct BackupGameResponse {
+    #[serde(flatten)]
+    pub game: GameResponse,
+    // From GameTurns, shaped like requests::TurnSnapshot
+    pub timeline: Vec<BackupTurn>,
+    pub minigames: Vec<MinigameResultResponse>
+}
+
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct BackupTurn {
+    pub turn: i32,
+    pub player_id: i32,
+    pub stars: Option<i32>,
+    pub coins: Option<i32>,
+    pub space: Option<String>
+}
+
+#[derive(Serialize, Debug)]
+pub struct RestoreResponse {
+    pub players: usize,
+    pub games: usize,
+    // Games in the backup that were already here, when merging
+    pub skipped_games: usize,
+    // Games that didn't pass the checks a new game has to, and weren't restored
+    pub rejected_games: Vec<RejectedGame>
+}
+
+// A game from a backup that wasn't restored, and why
+#[derive(Serialize, Debug)]
+pub struct RejectedGame {
+    // Its id in the backup
+    pub id: i32,
+    pub game: String,
+    pub reason: String
+}
+
+// A live game's state is shaped like requests::GameData, but might not be
+// a valid game yet
+#[derive(Serialize, Debug)]
+pub struct LiveGameResponse {
+    pub game_id: i32,
+    pub state: Value
+}
+
+// What a live game's websocket sends. Everyone watching gets the state after
+// every change, errors only go to whoever sent the change
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LiveMessage {
+    State {
+        game_id: i32,
+        state: Value
+    },
+    Error {
+        message: String
+    },
+    Finalized {
+        game_id: i32
+    },
+    Discarded {
+        game_id: i32
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct MinigameCatalogResponse {
+    pub game: String,
+    pub minigames: Vec<String>
+}
+
+// A minigame played in a game, from GET /api/games/:id/minigames
+#[derive(Serialize, Debug)]
+pub struct MinigameResultResponse {
+    pub turn: Option<i32>,
+    pub minigame: String,
+    #[serde(rename = "type")]
+    pub minigame_type: String,
+    pub players: Vec<PlayerResponse>,
+    pub winners: Vec<PlayerResponse>,
+    // The player on their own, in a 1v3 minigame
+    pub solo: Option<PlayerResponse>
+}
+
+#[derive(Serialize, Debug)]
+pub struct MinigamePlayerStats {
+    pub player_id: i32,
+    pub player_name: String,
+    pub played: i64,
+    pub wins: i64,
+    pub win_rate: f64
+}
+
+// One minigame of one title, from GET /api/stats/minigames
+#[derive(Serialize, Debug)]
+pub struct MinigameStats {
+    pub game: String,
+    pub minigame: String,
+    pub times_played: i64,
+    pub players: Vec<MinigamePlayerStats>
+}
+
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct GroupResponse {
+    pub id: i32,
+    pub name: String,
+    // Whether it's the user's own personal group
+    pub personal: bool,
+    // The user's role in the group
+    pub role: String
+}
+
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct GroupMember {
+    pub user_id: i32,
+    pub username: String,
+    pub role: String
+}
+
+// From GET /api/groups/:id
+#[derive(Serialize, Debug)]
+pub struct GroupDetails {
+    pub id: i32,
+    pub name: String,
+    pub personal: bool,
+    pub role: String,
+    pub members: Vec<GroupMember>
+}
+
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct InvitationResponse {
+    pub id: i32,
+    pub group_id: i32,
+    pub group_name: String,
+    // Who's invited
+    pub username: String,
+    // None if whoever sent it has since deleted their account
+    pub invited_by: Option<String>,
+    pub created_at: DateTime<Utc>
+}
+
+// From GET /api/admin/users
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct AdminUserResponse {
+    pub id: i32,
+    pub username: String,
+    #[sqlx(try_from = "String")]
+    pub role: Role,
+    pub disabled: bool
+}
@@ -1,12 +1,14 @@
 use async_trait::async_trait;
 use tokio::task;
 use std::collections::HashMap;
-use sqlx::postgres::PgPool;
+use sqlx::postgres::{PgConnection, PgPool};
 use sqlx::FromRow;
 use serde::{Serialize, Deserialize};
 use password_auth::{generate_hash, verify_password};
+use chrono::{DateTime, Utc};
 use axum::{
     Extension,
+    extract::{Path, Query},
     http::StatusCode,
     response::IntoResponse,
     Form,
@@ -16,11 +18,16 @@ use axum_login::{
     AuthUser,
     AuthnBackend,
     UserId};
-use crate::requests::{GameData, MarioPartyData};
-use crate::responses::MessageResponse;
+use crate::requests::{GameData, GamesQuery, GroupQuery, GroupRole, MarioPartyData, PasswordChange, Role, SortOrder};
+use crate::responses::{GameResponse, GamesResponse, MessageResponse, UserResponse};
 use crate::listfields::{ListFields, Field, EnumData, ObjectData};
+use crate::errors::ApiError;
+use crate::players::resolve_players;
+use crate::entries::{fetch_entries, insert_entries};
+use crate::ratings::recompute_from;
+use crate::groups::{member_group, member_role, personal_group};
 
-type AuthSession = axum_login::AuthSession<Backend>;
+pub type AuthSession = axum_login::AuthSession<Backend>;
 
 #[derive(Debug, Clone)]
 pub struct Backend {
@@ -38,6 +45,9 @@ pub struct User {
     id: i32,
     pub username: String,
     password_hash: String,
+    #[sqlx(try_from = "String")]
+    pub role: Role,
+    pub disabled: bool
 }
 
 // This allows us to extract the authentication fields from forms. We use this
@@ -65,6 +75,8 @@ impl std::fmt::Debug for User {
         f.debug_struct("User")
             .field("id", &self.id)
             .field("username", &self.username)
+            .field("role", &self.role)
+            .field("disabled", &self.disabled)
             .field("password", &"[redacted]")
             .finish()
     }
@@ -78,7 +90,7 @@ impl AuthUser for User {
     }
 
     fn session_auth_hash(&self) -> &[u8] {
-        &self.password_hash.as_bytes()
+        self.password_hash.as_bytes()
     }
 }
 
@@ -107,7 +119,8 @@ impl AuthnBackend for Backend {
 
 
     async fn get_user(&self, user_id: &UserId<Self>) -> Result<Option<Self::User>, Self::Error> {
-        let user = sqlx::query_as("SELECT * FROM users WHERE id = $1")
+        // Disabled users come back as nobody, which logs out every session they had
+        let user = sqlx::query_as("SELECT * FROM users WHERE id = $1 AND NOT disabled")
             .bind(user_id)
             .fetch_optional(&self.db)
             .await?;
@@ -116,559 +129,356 @@ impl AuthnBackend for Backend {
     }
 }
 
+pub fn signed_in_user(auth_session: AuthSession) -> Result<User, ApiError> {
+    auth_session.user.ok_or_else(|| {
+        println!("No user signed in");
+        ApiError::Unauthorized(String::from("Not signed in"))
+    })
+}
+
+/// Checks the things about a game that serde can't
+pub fn check_game(mp_data: &GameData) -> Result<(), ApiError> {
+    if !mp_data.player_data.is_valid_board(&mp_data.board) {
+        println!("Invalid board: {}", mp_data.board);
+        return Err(ApiError::BadRequest(format!("\"{}\" is not a board in this game", mp_data.board)));
+    }
+
+    check_stats(mp_data)
+}
+
+/// Everything check_game checks but the board, for games from before boards
+/// were kept
+pub fn check_stats(mp_data: &GameData) -> Result<(), ApiError> {
+    mp_data.validate(mp_data.turns).map_err(|message| {
+        println!("Invalid game: {message}");
+        ApiError::BadRequest(message)
+    })?;
+
+    mp_data.player_data.check_placements().map_err(|message| {
+        println!("Invalid placements");
+        ApiError::BadRequest(message)
+    })
+}
+
 #[axum::debug_handler]
 pub async fn games(
     Extension(pool): Extension<PgPool>,
     auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
     Json(mp_data): Json<GameData>
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     println!("data: {mp_data:?}");
-    let tx_result = pool.begin().await;
-    let game_id: i32;
-    let user_id: i32;
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id, group.group).await?;
+    check_game(&mp_data)?;
 
-    if auth_session.user.is_none() {
-        println!("No user signed in");
-        return (
-            StatusCode::UNAUTHORIZED,
-            Json(
-                MessageResponse {
-                    message: String::from("Not signed in"),
-                    success: false
-                }
-            )
-        ).into_response();
+    let date = mp_data.date;
+
+    let mut tx = pool.begin().await?;
+    insert_game(&mut tx, user.id, group_id, mp_data).await?;
+    recompute_from(&mut tx, group_id, date).await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(
+            MessageResponse {
+                message: String::from("Created game successfully"),
+                success: true
+            }
+        )
+    ))
+}
+
+/// Inserts a game that's already been through check_game into a group, along
+/// with its players' entries, and returns its id. `user_id` is whoever's
+/// adding it
+pub async fn insert_game(conn: &mut PgConnection, user_id: i32, group_id: i32, mut mp_data: GameData) -> Result<i32, ApiError> {
+    let game_id: i32 = sqlx::query_scalar("INSERT INTO Games (user_id, group_id, date, turns, board, game) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id")
+        .bind(user_id)
+        .bind(group_id)
+        .bind(mp_data.date)
+        .bind(mp_data.turns)
+        .bind(mp_data.board)
+        .bind(mp_data.player_data.game_name())
+        .fetch_one(&mut *conn)
+        .await?;
+
+    resolve_players(&mut *conn, group_id, &mut mp_data.player_data).await?;
+    insert_player_data(&mut *conn, game_id, mp_data.player_data).await?;
+
+    Ok(game_id)
+}
+
+// Inserts every player's entry for a game into the *Entries table for that game
+pub async fn insert_player_data(conn: &mut PgConnection, game_id: i32, player_data: MarioPartyData) -> Result<(), sqlx::Error> {
+    match player_data {
+        MarioPartyData::MarioParty(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty2(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty3(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty4(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty5(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty6(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty7(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty8(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty9(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioParty10(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioPartyDS(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioPartyIslandTour(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioPartyStarRush(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioPartyTop100(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::SuperMarioParty(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioPartySuperstars(players_data) => insert_entries(conn, game_id, players_data).await,
+        MarioPartyData::MarioPartyJamboree(players_data) => insert_entries(conn, game_id, players_data).await
     }
+}
 
-    let mut tx = match tx_result {
-        Ok(tx) => {
-            tx
-        }
-        Err(e) => {
-            println!("{}", e);
-            println!("Failed to create transaction");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    MessageResponse {
-                        message: String::from("Server error"),
-                        success: false
-                    }
-                )
-            ).into_response();
-        }
+#[derive(FromRow)]
+pub struct GameRow {
+    id: i32,
+    game: String,
+    board: Option<String>,
+    turns: i32,
+    date: DateTime<Utc>
+}
+
+// Reads a game's entries back out of the *Entries table for `game`.
+// Returns None if `game` isn't a game we know about
+async fn fetch_player_data(pool: &PgPool, game: &str, game_id: i32) -> Result<Option<MarioPartyData>, sqlx::Error> {
+    let player_data = match game {
+        "Mario Party" => MarioPartyData::MarioParty(fetch_entries(pool, game_id).await?),
+        "Mario Party 2" => MarioPartyData::MarioParty2(fetch_entries(pool, game_id).await?),
+        "Mario Party 3" => MarioPartyData::MarioParty3(fetch_entries(pool, game_id).await?),
+        "Mario Party 4" => MarioPartyData::MarioParty4(fetch_entries(pool, game_id).await?),
+        "Mario Party 5" => MarioPartyData::MarioParty5(fetch_entries(pool, game_id).await?),
+        "Mario Party 6" => MarioPartyData::MarioParty6(fetch_entries(pool, game_id).await?),
+        "Mario Party 7" => MarioPartyData::MarioParty7(fetch_entries(pool, game_id).await?),
+        "Mario Party 8" => MarioPartyData::MarioParty8(fetch_entries(pool, game_id).await?),
+        "Mario Party 9" => MarioPartyData::MarioParty9(fetch_entries(pool, game_id).await?),
+        "Mario Party 10" => MarioPartyData::MarioParty10(fetch_entries(pool, game_id).await?),
+        "Mario Party DS" => MarioPartyData::MarioPartyDS(fetch_entries(pool, game_id).await?),
+        "Mario Party: Island Tour" => MarioPartyData::MarioPartyIslandTour(fetch_entries(pool, game_id).await?),
+        "Mario Party: Star Rush" => MarioPartyData::MarioPartyStarRush(fetch_entries(pool, game_id).await?),
+        "Mario Party: The Top 100" => MarioPartyData::MarioPartyTop100(fetch_entries(pool, game_id).await?),
+        "Super Mario Party" => MarioPartyData::SuperMarioParty(fetch_entries(pool, game_id).await?),
+        "Mario Party Superstars" => MarioPartyData::MarioPartySuperstars(fetch_entries(pool, game_id).await?),
+        "Mario Party Jamboree" => MarioPartyData::MarioPartyJamboree(fetch_entries(pool, game_id).await?),
+        _ => return Ok(None)
     };
 
-    let user_id_result: Result<i32, sqlx::Error> = sqlx::query_scalar("SELECT id FROM Users WHERE username = $1")
-        .bind(auth_session.user.unwrap().username)
-        .fetch_one(&mut *tx)
-        .await;
+    Ok(Some(player_data))
+}
 
+pub async fn game_response(pool: &PgPool, row: GameRow) -> Result<Option<GameResponse>, sqlx::Error> {
+    let player_data = fetch_player_data(pool, &row.game, row.id).await?;
 
-    user_id = match user_id_result {
-        Ok(user_id) => {
-            user_id
-        }
-        Err(e) => {
-            println!("{}", e);
-            println!("Failed to find user");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    MessageResponse {
-                        message: String::from("Server error"),
-                        success: false
-                    }
-                )
-            ).into_response();
-        }
+    Ok(player_data.map(|player_data| GameResponse {
+        id: row.id,
+        player_data,
+        board: row.board,
+        turns: row.turns,
+        date: row.date
+    }))
+}
+
+#[axum::debug_handler]
+pub async fn list_games(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<GamesQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id, group.group).await?;
+
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
+
+    // Can't bind ASC/DESC, but it only ever comes from the enum so this is safe
+    let order = match params.sort {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC"
     };
 
-    let result: Result<i32, sqlx::Error> = sqlx::query_scalar("INSERT INTO Games VALUES (DEFAULT, $1, $2, $3) RETURNING id")
-        .bind(user_id)
-        .bind(mp_data.date)
-        .bind(mp_data.turns)
-        .fetch_one(&mut *tx)
-        .await;
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Games WHERE group_id = $1 AND game IS NOT NULL AND NOT draft")
+        .bind(group_id)
+        .fetch_one(&pool)
+        .await?;
+
+    let rows: Vec<GameRow> = sqlx::query_as(&format!("
+            SELECT id, game, board, turns, date FROM Games
+            WHERE group_id = $1 AND game IS NOT NULL AND NOT draft
+            ORDER BY date {order}, id {order}
+            LIMIT $2 OFFSET $3"))
+        .bind(group_id)
+        .bind(per_page)
+        .bind((page - 1) * per_page)
+        .fetch_all(&pool)
+        .await?;
 
-    match result {
-        Ok(res) => {
-            game_id = res;
-        }
-        Err(e) => {
-            println!("{}", e);
-            println!("Failed to insert game");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    MessageResponse {
-                        message: String::from("Server error"),
-                        success: false
-                    }
-                )
-            ).into_response();
+    let mut games = Vec::new();
+    for row in rows {
+        if let Some(game) = game_response(&pool, row).await? {
+            games.push(game);
         }
     }
- 
-    match mp_data.player_data {
-        MarioPartyData::MarioParty(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioPartyEntries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.peak_coins)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.question_spaces)
-                    .bind(player_data.minigame_spaces)
-                    .bind(player_data.exclaimation_spaces)
-                    .bind(player_data.mushroom_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty2(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty2Entries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.peak_coins)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.question_spaces)
-                    .bind(player_data.exclaimation_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.battle_spaces)
-                    .bind(player_data.item_spaces)
-                    .bind(player_data.bank_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty3(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty3Entries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.peak_coins)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.question_spaces)
-                    .bind(player_data.exclaimation_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.battle_spaces)
-                    .bind(player_data.item_spaces)
-                    .bind(player_data.bank_spaces)
-                    .bind(player_data.game_guy_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty4(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty4Entries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.peak_coins)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.happening_spaces)
-                    .bind(player_data.fortune_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.battle_spaces)
-                    .bind(player_data.mushroom_spaces)
-                    .bind(player_data.warp_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty5(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty5Entries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.peak_coins)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.capsule_spaces)
-                    .bind(player_data.question_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.dk_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty6(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty6Entries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.orbs_used)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.character_spaces)
-                    .bind(player_data.question_spaces)
-                    .bind(player_data.duel_spaces)
-                    .bind(player_data.miracle_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.dk_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty7(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty7Entries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.coins_spent_on_orbs)
-                    .bind(player_data.orbs_used)
-                    .bind(player_data.spaces_moved)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.character_spaces)
-                    .bind(player_data.green_spaces)
-                    .bind(player_data.duel_spaces)
-                    .bind(player_data.mic_spaces)
-                    .bind(player_data.dk_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty8(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty8Entries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.coins_spent_in_shop)
-                    .bind(player_data.candy_eaten)
-                    .bind(player_data.spaces_moved)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.green_spaces)
-                    .bind(player_data.lucky_spaces)
-                    .bind(player_data.dk_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty9(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty9Entries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.mini_stars)
-                    .bind(player_data.mini_stars_lost)
-                    .bind(player_data.minigame_stars)
-                    .bind(player_data.minigame_stars_lost)
-                    .bind(player_data.event_mini_stars)
-                    .bind(player_data.event_mini_stars_lost)
-                    .bind(player_data.bonus_star_mini_stars)
-                    .bind(player_data.dice_block_spaces)
-                    .bind(player_data.lucky_spaces)
-                    .bind(player_data.unlucky_spaces)
-                    .bind(player_data.spin_spaces)
-                    .bind(player_data.event_spaces)
-                    .bind(player_data.shuffle_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.captain_event_spaces)
-                    .bind(player_data.boss_battle_spaces)
-                    .bind(player_data.dash_spaces)
-                    .bind(player_data.back_spaces)
-                    .bind(player_data.free_for_all_spaces)
-                    .bind(player_data.one_v_three_spaces)
-                    .bind(player_data.battle_spaces)
-                    .bind(player_data.bowser_jr_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioParty10(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioParty10Entries VALUES (DEFAULT, $1, $2, $3, $4)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.mini_stars)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioPartyDS(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioPartyDSEntries VALUES (DEFAULT, $1, $2, $3, $4, $5)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioPartyIslandTour(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioPartyIslandTourEntries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.green_spaces)
-                    .bind(player_data.item_spaces)
-                    .bind(player_data.dash_spaces)
-                    .bind(player_data.blue_event_spaces)
-                    .bind(player_data.piranha_plant_spaces)
-                    .bind(player_data.red_event_spaces)
-                    .bind(player_data.free_for_all_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.spaces_moved)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioPartyStarRush(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioPartyStarRushEntries VALUES (DEFAULT, $1, $2, $3, $4, $5)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioPartyTop100(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioPartyTop100Entries VALUES (DEFAULT, $1, $2, $3, $4, $5)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::SuperMarioParty(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO SuperMarioPartyEntries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.hidden_blocks)
-                    .bind(player_data.items_used)
-                    .bind(player_data.allies)
-                    .bind(player_data.spaces_moved)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.lucky_spaces)
-                    .bind(player_data.vs_spaces)
-                    .bind(player_data.event_spaces)
-                    .bind(player_data.item_spaces)
-                    .bind(player_data.ally_spaces)
-                    .bind(player_data.bad_luck_spaces)
-                    .bind(player_data.extra_bad_luck_spaces)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioPartySuperstars(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioPartySuperstarsEntries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.minigame_coins)
-                    .bind(player_data.hidden_blocks)
-                    .bind(player_data.items_bought)
-                    .bind(player_data.items_used)
-                    .bind(player_data.spaces_moved)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.item_spaces)
-                    .bind(player_data.event_spaces)
-                    .bind(player_data.chance_time_spaces)
-                    .bind(player_data.lucky_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.vs_spaces)
-                    .bind(player_data.koopa_bank_spaces)
-                    .bind(player_data.stickers_used)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
-        },
-        MarioPartyData::MarioPartyJamboree(players_data) => {
-            for player_data in players_data {
-                let err = sqlx::query("INSERT INTO MarioPartyJamboreeEntries VALUES (DEFAULT, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22)")
-                    .bind(game_id)
-                    .bind(player_data.player_name)
-                    .bind(player_data.character)
-                    .bind(player_data.stars)
-                    .bind(player_data.coins)
-                    .bind(player_data.blue_spaces)
-                    .bind(player_data.red_spaces)
-                    .bind(player_data.lucky_spaces)
-                    .bind(player_data.unlucky_spaces)
-                    .bind(player_data.item_spaces)
-                    .bind(player_data.bowser_spaces)
-                    .bind(player_data.event_spaces)
-                    .bind(player_data.chance_time_spaces)
-                    .bind(player_data.vs_spaces)
-                    .bind(player_data.coins_received)
-                    .bind(player_data.minigames_won)
-                    .bind(player_data.hidden_blocks_found)
-                    .bind(player_data.showdown_minigames_won)
-                    .bind(player_data.items_bought)
-                    .bind(player_data.items_used)
-                    .bind(player_data.spaces_traveled)
-                    .bind(player_data.reactions_used)
-                    .execute(&mut *tx)
-                    .await;
-
-                err.unwrap();
-            }
+
+    Ok(Json(
+        GamesResponse {
+            games,
+            page,
+            per_page,
+            total
         }
-    }
-    tx.commit().await.unwrap();
+    ))
+}
 
-    return (
-        StatusCode::OK,
-        Json(
-            MessageResponse {
-                message: String::from("Created game successfully"),
-                success: true
-            }
-        )
-    ).into_response();
+#[axum::debug_handler]
+pub async fn game(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let row: Option<GameRow> = sqlx::query_as("
+            SELECT id, game, board, turns, date FROM Games
+            WHERE id = $1 AND game IS NOT NULL AND NOT draft
+                AND group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $2)")
+        .bind(game_id)
+        .bind(user.id)
+        .fetch_optional(&pool)
+        .await?;
+
+    let game = match row {
+        Some(row) => game_response(&pool, row).await?,
+        None => None
+    };
 
+    game.map(Json).ok_or_else(|| ApiError::NotFound(String::from("Game not found")))
 }
 
-fn fix_enum_name(s: String) -> String {
-    let mut new_string = "".to_string();
-    let mut prev_char = 'a';
-    for (i, char) in s.chars().enumerate() {
-        if i != 0 {
-            if char.is_ascii_uppercase() && !prev_char.is_ascii_uppercase() {
-                new_string.push(' ');
-            }
-            else if char.is_digit(10) && !prev_char.is_digit(10) {
-                new_string.push(' ');
-            }
-        }
+/// Locks the Games row for the rest of the transaction and checks that
+/// `user_id` can change it, which whoever added it and the owners of its
+/// group can. Returns the game the row is for, so we know which *Entries
+/// table it uses, and the group it's in
+pub async fn owned_game(conn: &mut PgConnection, game_id: i32, user_id: i32) -> Result<(Option<String>, i32), ApiError> {
+    let row: Option<(Option<i32>, Option<i32>, Option<String>)> = sqlx::query_as("SELECT user_id, group_id, game FROM Games WHERE id = $1 FOR UPDATE")
+        .bind(game_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let Some((added_by, Some(group_id), game)) = row else {
+        return Err(ApiError::NotFound(String::from("Game not found")));
+    };
 
-        new_string.push(char);
-        prev_char = char;
+    match member_role(conn, group_id, user_id).await? {
+        Some(GroupRole::Owner) => Ok((game, group_id)),
+        Some(GroupRole::Member) if added_by == Some(user_id) => Ok((game, group_id)),
+        Some(GroupRole::Member) => {
+            println!("Game was added by someone else");
+            Err(ApiError::Forbidden(String::from("Only whoever added that game or an owner of its group can change it")))
+        },
+        None => {
+            println!("Game belongs to another group");
+            Err(ApiError::Forbidden(String::from("That game belongs to a group you're not in")))
+        }
     }
-
-    new_string
 }
 
 #[axum::debug_handler]
-pub async fn characters(
-    Extension(pool): Extension<PgPool>
-) -> impl IntoResponse {
-    let enums: Vec<(String, String)> = sqlx::query_as("
-            SELECT
-               pg_type.typname AS enum_name,
-               pg_enum.enumlabel AS enum_value
-            from pg_type
-               JOIN pg_enum on pg_type.oid = pg_enum.enumtypid
-               WHERE pg_type.typname LIKE '%Characters'")
-        .fetch_all(&pool)
-        .await
-        .unwrap();
+pub async fn update_game(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>,
+    Json(mut mp_data): Json<GameData>
+) -> Result<impl IntoResponse, ApiError> {
+    println!("data: {mp_data:?}");
+    let user = signed_in_user(auth_session)?;
+    check_game(&mp_data)?;
+
+    let mut tx = pool.begin().await?;
+
+    let (old_game, group_id) = owned_game(&mut tx, game_id, user.id).await?;
+    let (old_date, draft): (DateTime<Utc>, bool) = sqlx::query_as("SELECT date, draft FROM Games WHERE id = $1")
+        .bind(game_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if draft {
+        return Err(ApiError::BadRequest(String::from("That game is still being played, finish it from /api/live first")));
+    }
+    let date = mp_data.date;
+
+    // The game itself might be what changed, so the old entries could be in
+    // a different table than the new ones
+    if let Some(table) = old_game.as_deref().and_then(MarioPartyData::entries_table) {
+        sqlx::query(&format!("DELETE FROM {table} WHERE game_id = $1"))
+            .bind(game_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("UPDATE Games SET date = $1, turns = $2, board = $3, game = $4 WHERE id = $5")
+        .bind(mp_data.date)
+        .bind(mp_data.turns)
+        .bind(mp_data.board)
+        .bind(mp_data.player_data.game_name())
+        .bind(game_id)
+        .execute(&mut *tx)
+        .await?;
 
-    let mut characters: HashMap<String, Vec::<String>> = HashMap::new();
-    for row in enums {
-        let enum_name = row.0;
-        let enum_value = row.1;
+    resolve_players(&mut tx, group_id, &mut mp_data.player_data).await?;
+    insert_player_data(&mut tx, game_id, mp_data.player_data).await?;
 
-        let enum_name = enum_name.strip_suffix("Characters").unwrap().to_string();
+    // Moving a game's date changes the ratings from whichever date is earlier
+    recompute_from(&mut tx, group_id, old_date.min(date)).await?;
 
-        let enum_name = fix_enum_name(enum_name);
+    tx.commit().await?;
 
-        if !characters.contains_key(&enum_name) {
-            characters.insert(enum_name.clone(), Vec::<String>::new());
-        }
-        if let Some(this_games_characters) = characters.get_mut(&enum_name) {
-            this_games_characters.push(enum_value);
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Updated game successfully"),
+            success: true
         }
+    ))
+}
+
+#[axum::debug_handler]
+pub async fn delete_game(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+
+    let (_, group_id) = owned_game(&mut tx, game_id, user.id).await?;
+
+    // The live game would still be open for anyone watching it
+    let draft: bool = sqlx::query_scalar("SELECT draft FROM Games WHERE id = $1")
+        .bind(game_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if draft {
+        return Err(ApiError::BadRequest(String::from("That game is still being played, finish or abandon it from /api/live")));
     }
 
-    return Json(characters)
+    // The *Entries and RatingHistory rows go with it through ON DELETE CASCADE
+    let date: DateTime<Utc> = sqlx::query_scalar("DELETE FROM Games WHERE id = $1 RETURNING date")
+        .bind(game_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    recompute_from(&mut tx, group_id, date).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Deleted game successfully"),
+            success: true
+        }
+    ))
 }
 
 #[axum::debug_handler]
@@ -676,102 +486,153 @@ pub async fn signup(
     Extension(pool): Extension<PgPool>,
     mut auth_session: AuthSession,
     Form(creds): Form<Credentials>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let user_exists = sqlx::query("SELECT 1 FROM users WHERE username = $1")
         .bind(creds.username.clone())
         .fetch_optional(&pool)
-        .await
-        .unwrap();
-
-    match user_exists {
-        Some(_record) => {
-            println!("Already exists");
-            return (
-                StatusCode::FORBIDDEN,
-                Json(
-                    MessageResponse {
-                        message: String::from("That username already exists"),
-                        success: false
-                    }
-                )
-            ).into_response();
-        },
-        None => {
-            println!("Creating user");
-            sqlx::query("INSERT INTO users (username, password_hash) VALUES ($1, $2)")
-                .bind(creds.username.clone())
-                .bind(generate_hash(creds.password.clone()))
-                .fetch_optional(&pool)
-                .await
-                .unwrap();
-
-            let user = match auth_session.authenticate(creds.clone()).await {
-                Ok(Some(user)) => user,
-                Ok(None) => {
-                    println!("Failed to auth after user creation??");
-                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(
-                        MessageResponse {
-                            message: String::from("Unknown error, try logging in at /login"),
-                            success: false
-                        }
-                    )).into_response();
-                }
-                Err(error) => {
-                    println!("error: {}", error);
-                    return StatusCode::INTERNAL_SERVER_ERROR.into_response()
-                }
-            };
-
-            if auth_session.login(&user).await.is_err() {
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-            }
+        .await?;
 
-            return (
-                StatusCode::OK,
-                Json(
-                    MessageResponse {
-                        message: String::from("User created"),
-                        success: true
-                    }
-                )
-            ).into_response();
-        }
+    if user_exists.is_some() {
+        println!("Already exists");
+        return Err(ApiError::Conflict(String::from("That username already exists")));
     }
+
+    println!("Creating user");
+    let mut tx = pool.begin().await?;
+    // The first user to sign up is the one running the instance, so they're its admin
+    let user_id: i32 = sqlx::query_scalar("
+            INSERT INTO users (username, password_hash, role)
+            VALUES ($1, $2, CASE WHEN EXISTS (SELECT 1 FROM users) THEN 'user' ELSE 'admin' END)
+            RETURNING id")
+        .bind(creds.username.clone())
+        .bind(generate_hash(creds.password.clone()))
+        .fetch_one(&mut *tx)
+        .await?;
+    personal_group(&mut tx, user_id).await?;
+    tx.commit().await?;
+
+    let user = match auth_session.authenticate(creds.clone()).await? {
+        Some(user) => user,
+        None => {
+            println!("Failed to auth after user creation??");
+            return Err(ApiError::Internal(String::from("Failed to authenticate a user we just created")));
+        }
+    };
+
+    auth_session.login(&user).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(
+            MessageResponse {
+                message: String::from("User created"),
+                success: true
+            }
+        )
+    ))
 }
 
 #[axum::debug_handler]
 pub async fn login(
     mut auth_session: AuthSession,
     Form(creds): Form<Credentials>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     println!("login");
-    let user = match auth_session.authenticate(creds.clone()).await {
-        Ok(Some(user)) => user,
-        Ok(None) => {
+    let user = match auth_session.authenticate(creds.clone()).await? {
+        Some(user) => user,
+        None => {
             println!("Unauthorized!");
-            return (StatusCode::UNAUTHORIZED, Json(
-                MessageResponse {
-                    message: String::from("Authorization failed"),
-                    success: false
-                }
-            )).into_response();
-        },
-        Err(error) => {
-            println!("error: {}", error);
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            return Err(ApiError::Unauthorized(String::from("Authorization failed")));
         }
     };
 
-    if auth_session.login(&user).await.is_err() {
-        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    if user.disabled {
+        println!("Disabled user {} tried to log in", user.username);
+        return Err(ApiError::Forbidden(String::from("This account has been disabled")));
     }
 
-    return (StatusCode::OK, Json(
+    auth_session.login(&user).await?;
+
+    Ok((StatusCode::OK, Json(
         MessageResponse {
             message: String::from("Success!"),
             success: true
         }
-    )).into_response();
+    )))
+}
+
+#[axum::debug_handler]
+pub async fn logout(
+    mut auth_session: AuthSession
+) -> Result<impl IntoResponse, ApiError> {
+    println!("logout");
+    auth_session.logout().await?;
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Logged out"),
+            success: true
+        }
+    ))
+}
+
+#[axum::debug_handler]
+pub async fn me(
+    auth_session: AuthSession
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    Ok(Json(
+        UserResponse {
+            id: user.id,
+            username: user.username,
+            role: user.role
+        }
+    ))
+}
+
+#[axum::debug_handler]
+pub async fn change_password(
+    Extension(pool): Extension<PgPool>,
+    mut auth_session: AuthSession,
+    Form(change): Form<PasswordChange>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session.clone())?;
+
+    if change.new_password.is_empty() {
+        return Err(ApiError::BadRequest(String::from("The new password can't be empty")));
+    }
+
+    // Hashing is slow on purpose, so it's kept off the async threads like in authenticate
+    let password_hash = user.password_hash.clone();
+    let new_hash = task::spawn_blocking(move || {
+        verify_password(change.current_password, &password_hash)
+            .map(|_| generate_hash(change.new_password))
+    })
+    .await
+    .map_err(|err| ApiError::Internal(err.to_string()))?
+    .map_err(|_| {
+        println!("Wrong current password");
+        ApiError::Forbidden(String::from("The current password is wrong"))
+    })?;
+
+    let user: User = sqlx::query_as("UPDATE users SET password_hash = $1 WHERE id = $2 RETURNING *")
+        .bind(new_hash)
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await?;
+
+    // Every session remembers the session_auth_hash of the user it was logged
+    // in as, and that was the old password hash. Logging in again updates this
+    // one, and the rest stop working the next time they're used.
+    auth_session.login(&user).await?;
+
+    Ok(Json(
+        MessageResponse {
+            message: String::from("Password changed"),
+            success: true
+        }
+    ))
 }
 
 #[axum::debug_handler]
@@ -784,14 +645,12 @@ pub async fn input_schema() -> impl IntoResponse {
         // MarioParty2(Vec<MarioParty2>),
         // etc.
         for variant in variants {
-            match variant.type_data {
-                Some(ObjectData::Fields(fields)) => {
-                    h.insert(variant.name, fields);
-                },
-                _ => {}
+            if let Some(ObjectData::Fields(fields)) = variant.type_data {
+                h.insert(variant.name, fields);
             }
         }
     }
 
-    return Json(h);
+    Json(h)
 }
+
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use sqlx::postgres::PgPool;
+use axum_login::tower_sessions::{
+    cookie::time::{Duration, OffsetDateTime},
+    session::{Id, Record},
+    session_store::{self, ExpiredDeletion, SessionStore}
+};
+
+/// Keeps sessions in the Sessions table so people stay logged in when the
+/// server restarts.
+///
+/// Besides the expiry date the session layer sets, which moves forward as the
+/// session is used, a session is never good for longer than `lifetime` after
+/// it was created.
+#[derive(Debug, Clone)]
+pub struct PgSessionStore {
+    pool: PgPool,
+    lifetime: Duration
+}
+
+impl PgSessionStore {
+    pub fn new(pool: PgPool, lifetime: Duration) -> Self {
+        Self { pool, lifetime }
+    }
+
+    // Sessions created before this are too old to use, however recently they were active
+    fn oldest_allowed(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc() - self.lifetime
+    }
+
+    /// Deletes expired sessions every `period`, forever. Meant to be spawned as a task.
+    pub async fn continuously_delete_expired(self, period: std::time::Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.delete_expired().await {
+                println!("Failed to delete expired sessions: {err}");
+            }
+        }
+    }
+}
+
+fn backend_error(err: sqlx::Error) -> session_store::Error {
+    session_store::Error::Backend(err.to_string())
+}
+
+#[async_trait]
+impl SessionStore for PgSessionStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        let data = serde_json::to_string(&record.data)
+            .map_err(|err| session_store::Error::Encode(err.to_string()))?;
+
+        loop {
+            let result = sqlx::query("INSERT INTO Sessions (id, data, expiry_date) VALUES ($1, $2, $3) ON CONFLICT (id) DO NOTHING")
+                .bind(record.id.to_string())
+                .bind(&data)
+                .bind(record.expiry_date)
+                .execute(&self.pool)
+                .await
+                .map_err(backend_error)?;
+
+            if result.rows_affected() == 1 {
+                return Ok(());
+            }
+
+            // Someone already has this id, so try another
+            record.id = Id::default();
+        }
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        let data = serde_json::to_string(&record.data)
+            .map_err(|err| session_store::Error::Encode(err.to_string()))?;
+
+        sqlx::query("
+                INSERT INTO Sessions (id, data, expiry_date) VALUES ($1, $2, $3)
+                ON CONFLICT (id) DO UPDATE SET data = EXCLUDED.data, expiry_date = EXCLUDED.expiry_date")
+            .bind(record.id.to_string())
+            .bind(data)
+            .bind(record.expiry_date)
+            .execute(&self.pool)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        let row: Option<(String, OffsetDateTime)> = sqlx::query_as("
+                SELECT data, expiry_date FROM Sessions
+                WHERE id = $1 AND expiry_date > now() AND created_at > $2")
+            .bind(session_id.to_string())
+            .bind(self.oldest_allowed())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(backend_error)?;
+
+        match row {
+            Some((data, expiry_date)) => {
+                let data = serde_json::from_str(&data)
+                    .map_err(|err| session_store::Error::Decode(err.to_string()))?;
+
+                Ok(Some(Record {
+                    id: *session_id,
+                    data,
+                    expiry_date
+                }))
+            },
+            None => Ok(None)
+        }
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        sqlx::query("DELETE FROM Sessions WHERE id = $1")
+            .bind(session_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExpiredDeletion for PgSessionStore {
+    async fn delete_expired(&self) -> session_store::Result<()> {
+        let result = sqlx::query("DELETE FROM Sessions WHERE expiry_date <= now() OR created_at <= $1")
+            .bind(self.oldest_allowed())
+            .execute(&self.pool)
+            .await
+            .map_err(backend_error)?;
+
+        if result.rows_affected() > 0 {
+            println!("Deleted {} expired sessions", result.rows_affected());
+        }
+
+        Ok(())
+    }
+}
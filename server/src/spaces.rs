@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashSet};
+use sqlx::postgres::PgPool;
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::Query,
+    response::IntoResponse,
+    Json
+};
+use crate::listfields::Field;
+use crate::requests::{GroupQuery, MarioPartyData, SpaceStatsQuery};
+use crate::responses::{PlayerSpaces, SpaceStats};
+use crate::errors::ApiError;
+use crate::requests::titles;
+use crate::routes::{AuthSession, signed_in_user};
+use crate::groups::member_group;
+
+#[derive(sqlx::FromRow)]
+struct SpaceRow {
+    game_id: i32,
+    player_id: i32,
+    player_name: String,
+    turns: i32,
+    placement: i32,
+    // In the same order as the title's space fields
+    spaces: Vec<f64>
+}
+
+// Pearson's correlation, or None if there are fewer than two points or
+// either side never changes
+fn correlation(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+    for (x, y) in points {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+        variance_y += (y - mean_y).powi(2);
+    }
+
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return None;
+    }
+
+    Some(covariance / (variance_x * variance_y).sqrt())
+}
+
+/// The space stats for one title. `spaces` are its space fields, the ones
+/// marked per_turn, since you only land on one space a turn.
+fn title_space_stats(game: String, spaces: &[Field], rows: Vec<SpaceRow>) -> SpaceStats {
+    let games_played = rows.iter().map(|row| row.game_id).collect::<HashSet<_>>().len() as i64;
+
+    let mut players: BTreeMap<i32, PlayerSpaces> = BTreeMap::new();
+    for row in &rows {
+        let player = players.entry(row.player_id).or_insert_with(|| PlayerSpaces {
+            player_id: row.player_id,
+            player_name: row.player_name.clone(),
+            games_played: 0,
+            turns_played: 0,
+            spaces: spaces.iter().map(|space| (space.name.clone(), 0.0)).collect()
+        });
+
+        player.games_played += 1;
+        player.turns_played += row.turns as i64;
+        for (space, landings) in spaces.iter().zip(&row.spaces) {
+            *player.spaces.entry(space.name.clone()).or_default() += landings;
+        }
+    }
+
+    let mut players: Vec<PlayerSpaces> = players.into_values().collect();
+    for player in &mut players {
+        let turns = player.turns_played.max(1) as f64;
+        for landings in player.spaces.values_mut() {
+            *landings /= turns;
+        }
+    }
+    players.sort_by_key(|player| player.player_name.to_lowercase());
+
+    // Each entry is a point, so a player with more games counts for more
+    let correlations: BTreeMap<String, Option<f64>> = spaces.iter().enumerate().map(|(i, space)| {
+        let points: Vec<(f64, f64)> = rows.iter()
+            .map(|row| (row.spaces[i] / row.turns.max(1) as f64, row.placement as f64))
+            .collect();
+        (space.name.clone(), correlation(&points))
+    }).collect();
+
+    SpaceStats {
+        game,
+        games_played,
+        players,
+        correlations
+    }
+}
+
+/// How often each player lands on each kind of space, per turn, in every
+/// title, and how landing on each kind of space goes with placement. Which
+/// fields are spaces comes from ListFields, so new titles are picked up
+/// without anything here changing.
+#[axum::debug_handler]
+pub async fn space_stats(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<SpaceStatsQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+
+    let titles: Vec<(String, Vec<Field>)> = titles().into_iter()
+        .filter(|(name, _)| params.game.as_ref().is_none_or(|game| game == name))
+        .collect();
+    if let (Some(game), true) = (&params.game, titles.is_empty()) {
+        return Err(ApiError::BadRequest(format!("\"{}\" is not a game", game)));
+    }
+
+    let mut stats = Vec::new();
+    for (game, fields) in titles {
+        let spaces: Vec<Field> = fields.into_iter().filter(|field| field.per_turn).collect();
+        let Some(table) = MarioPartyData::entries_table(&game) else {
+            continue;
+        };
+        if spaces.is_empty() {
+            continue;
+        }
+
+        let columns: Vec<String> = spaces.iter().map(|space| format!("e.{}::float8", space.name)).collect();
+        let rows: Vec<SpaceRow> = sqlx::query_as(&format!("
+                SELECT
+                    Games.id AS game_id,
+                    e.player_id,
+                    Players.name AS player_name,
+                    Games.turns,
+                    e.placement,
+                    ARRAY[{}] AS spaces
+                FROM {table} e
+                    JOIN Games ON Games.id = e.game_id
+                    JOIN Players ON Players.id = e.player_id
+                WHERE Games.group_id = $1
+                    AND ($2::timestamptz IS NULL OR Games.date >= $2)
+                    AND ($3::timestamptz IS NULL OR Games.date <= $3)",
+                columns.join(", ")))
+            .bind(group_id)
+            .bind(params.from)
+            .bind(params.to)
+            .fetch_all(&pool)
+            .await?;
+
+        // Without a game, only the ones that have been played
+        if rows.is_empty() && params.game.is_none() {
+            continue;
+        }
+
+        stats.push(title_space_stats(game, &spaces, rows));
+    }
+
+    Ok(Json(stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Option<f64>, b: f64) -> bool {
+        a.is_some_and(|a| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn perfect_correlations() {
+        assert!(close(correlation(&[(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)]), 1.0));
+        assert!(close(correlation(&[(1.0, 4.0), (2.0, 3.0), (3.0, 2.0), (4.0, 1.0)]), -1.0));
+    }
+
+    #[test]
+    fn uncorrelated() {
+        assert!(close(correlation(&[(1.0, 1.0), (2.0, 2.0), (3.0, 2.0), (4.0, 1.0)]), 0.0));
+    }
+
+    #[test]
+    fn partial_correlation() {
+        assert!(close(correlation(&[(1.0, 1.0), (2.0, 3.0), (3.0, 2.0)]), 0.5));
+    }
+
+    #[test]
+    fn not_enough_to_go_on() {
+        assert_eq!(correlation(&[]), None);
+        assert_eq!(correlation(&[(1.0, 2.0)]), None);
+        assert_eq!(correlation(&[(1.0, 2.0), (1.0, 3.0)]), None);
+        assert_eq!(correlation(&[(1.0, 2.0), (3.0, 2.0)]), None);
+    }
+}
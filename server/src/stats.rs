@@ -0,0 +1,322 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use sqlx::postgres::PgPool;
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::Query,
+    response::IntoResponse,
+    Json
+};
+use crate::requests::{
+    CharacterStatsQuery, GroupQuery, HeadToHeadQuery, LeaderboardQuery,
+    MarioParty, MarioParty2, MarioParty3, MarioParty4, MarioParty5, MarioParty6, MarioParty7, MarioParty8, MarioParty9, MarioParty10,
+    MarioPartyDS, MarioPartyIslandTour, MarioPartyStarRush, MarioPartyTop100, SuperMarioParty, MarioPartySuperstars, MarioPartyJamboree
+};
+use crate::entries::EntryTable;
+use crate::responses::{CharacterStats, CharacterStatsResponse, HeadToHead, HeadToHeadGame, LeaderboardEntry, PlayerResponse};
+use crate::listfields::{EnumData, ObjectData};
+use crate::requests::titles;
+use crate::errors::ApiError;
+use crate::routes::{AuthSession, signed_in_user};
+use crate::groups::member_group;
+
+/// What counts as stars in stats for one title.
+pub struct Scoring {
+    /// The name of the game, as in the Games.game column
+    pub game: &'static str,
+    /// The *Entries table holding the game's player data
+    pub table: &'static str,
+    /// The column counted as "stars" in stats, or None if the game doesn't have stars
+    pub stars: Option<&'static str>,
+    /// The coins column, or None if the game doesn't keep coins
+    pub coins: Option<&'static str>
+}
+
+/// Who won a game is whoever has a placement of 1. Placements are entered
+/// with the game, so tiebreaks and how each game decides its winner are
+/// already accounted for. Entries from before placements were recorded had
+/// one guessed for them, see the add_placement_and_bonus_stars migration.
+///
+/// Stars are regular stars, except for Mario Party 9 and 10 which only have
+/// mini stars. Mario Party: Island Tour has neither. Those three don't have
+/// coins either.
+pub const SCORING: [Scoring; 17] = [
+    Scoring { game: "Mario Party", table: MarioParty::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party 2", table: MarioParty2::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party 3", table: MarioParty3::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party 4", table: MarioParty4::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party 5", table: MarioParty5::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party 6", table: MarioParty6::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party 7", table: MarioParty7::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party 8", table: MarioParty8::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party 9", table: MarioParty9::TABLE, stars: Some("mini_stars"), coins: None },
+    Scoring { game: "Mario Party 10", table: MarioParty10::TABLE, stars: Some("mini_stars"), coins: None },
+    Scoring { game: "Mario Party DS", table: MarioPartyDS::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party: Island Tour", table: MarioPartyIslandTour::TABLE, stars: None, coins: None },
+    Scoring { game: "Mario Party: Star Rush", table: MarioPartyStarRush::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party: The Top 100", table: MarioPartyTop100::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Super Mario Party", table: SuperMarioParty::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party Superstars", table: MarioPartySuperstars::TABLE, stars: Some("stars"), coins: Some("coins") },
+    Scoring { game: "Mario Party Jamboree", table: MarioPartyJamboree::TABLE, stars: Some("stars"), coins: Some("coins") }
+];
+
+/// Builds a query over every entry of every game in `scorings`, with the
+/// columns game_id, game, date, player_id, player_name, stars and placement.
+/// player_name is the player's current name, not what they went by in that game.
+///
+/// The games are filtered to ones in the group in $1, and played
+/// between $2 and $3 if those aren't null.
+pub fn ranked_entries_query(scorings: &[&Scoring]) -> String {
+    let selects: Vec<String> = scorings.iter().map(|scoring| {
+        format!("
+            SELECT
+                Games.id AS game_id,
+                Games.game,
+                Games.date,
+                e.player_id,
+                Players.name AS player_name,
+                {stars}::float8 AS stars,
+                e.placement
+            FROM {table} e
+                JOIN Games ON Games.id = e.game_id
+                JOIN Players ON Players.id = e.player_id
+            WHERE Games.group_id = $1
+                AND ($2::timestamptz IS NULL OR Games.date >= $2)
+                AND ($3::timestamptz IS NULL OR Games.date <= $3)",
+            stars = scoring.stars.map(|stars| format!("e.{stars}")).unwrap_or(String::from("NULL")),
+            table = scoring.table
+        )
+    }).collect();
+
+    selects.join("\n            UNION ALL")
+}
+
+// Looks up the scorings for the `game` filter, or all of them if there isn't one
+pub fn scorings_for(game: Option<&str>) -> Result<Vec<&'static Scoring>, ApiError> {
+    match game {
+        Some(game) => {
+            match SCORING.iter().find(|scoring| scoring.game == game) {
+                Some(scoring) => Ok(vec![scoring]),
+                None => Err(ApiError::BadRequest(format!("\"{}\" is not a game", game)))
+            }
+        },
+        None => Ok(SCORING.iter().collect())
+    }
+}
+
+#[axum::debug_handler]
+pub async fn leaderboard(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<LeaderboardQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+    let scorings = scorings_for(params.game.as_deref())?;
+
+    let leaderboard: Vec<LeaderboardEntry> = sqlx::query_as(&format!("
+            WITH entries AS ({})
+            SELECT
+                player_id,
+                player_name,
+                COUNT(*) AS games_played,
+                COUNT(*) FILTER (WHERE placement = 1) AS wins,
+                AVG(placement)::float8 AS average_placement,
+                AVG(stars)::float8 AS average_stars
+            FROM entries
+            GROUP BY player_id, player_name
+            ORDER BY wins DESC, average_placement ASC, player_name ASC",
+            ranked_entries_query(&scorings)))
+        .bind(group_id)
+        .bind(params.from)
+        .bind(params.to)
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(leaderboard))
+}
+
+// Every character in `game`, in the order of its *Characters enum
+fn characters(game: &str) -> Vec<String> {
+    titles().into_iter()
+        .find(|(name, _)| name == game)
+        .and_then(|(_, fields)| fields.into_iter().find(|field| field.name == "character"))
+        .and_then(|field| match field.type_data {
+            Some(ObjectData::EnumData(EnumData { name: _, variants })) => {
+                Some(variants.into_iter().map(|variant| variant.name).collect())
+            },
+            _ => None
+        })
+        .unwrap_or_default()
+}
+
+#[derive(sqlx::FromRow)]
+struct CharacterRow {
+    character: String,
+    picks: i64,
+    wins: i64,
+    average_stars: Option<f64>,
+    average_coins: Option<f64>
+}
+
+/// Pick rate, win rate, average stars and average coins for every character
+/// in one game. The characters come from the game's *Characters enum, so
+/// ones nobody has played yet are there too, with zeros.
+#[axum::debug_handler]
+pub async fn character_stats(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<CharacterStatsQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+    let game = params.game.ok_or_else(|| ApiError::BadRequest(String::from("Pick a game with ?game=")))?;
+    let scoring = scorings_for(Some(&game))?[0];
+
+    let column = |column: Option<&str>| column.map(|column| format!("AVG(e.{column})::float8")).unwrap_or(String::from("NULL::float8"));
+    let rows: Vec<CharacterRow> = sqlx::query_as(&format!("
+            SELECT
+                e.character::text AS character,
+                COUNT(*) AS picks,
+                COUNT(*) FILTER (WHERE e.placement = 1) AS wins,
+                {stars} AS average_stars,
+                {coins} AS average_coins
+            FROM {table} e
+                JOIN Games ON Games.id = e.game_id
+            WHERE Games.group_id = $1
+                AND ($2::timestamptz IS NULL OR Games.date >= $2)
+                AND ($3::timestamptz IS NULL OR Games.date <= $3)
+            GROUP BY e.character",
+            stars = column(scoring.stars),
+            coins = column(scoring.coins),
+            table = scoring.table))
+        .bind(group_id)
+        .bind(params.from)
+        .bind(params.to)
+        .fetch_all(&pool)
+        .await?;
+
+    let games_played: i64 = sqlx::query_scalar("
+            SELECT COUNT(*) FROM Games
+            WHERE group_id = $1 AND game = $2 AND NOT draft
+                AND ($3::timestamptz IS NULL OR date >= $3)
+                AND ($4::timestamptz IS NULL OR date <= $4)")
+        .bind(group_id)
+        .bind(&game)
+        .bind(params.from)
+        .bind(params.to)
+        .fetch_one(&pool)
+        .await?;
+
+    let mut rows: HashMap<String, CharacterRow> = rows.into_iter().map(|row| (row.character.clone(), row)).collect();
+    let rate = |count: i64, total: i64| if total == 0 { 0.0 } else { count as f64 / total as f64 };
+    let characters = characters(&game).into_iter().map(|character| {
+        let row = rows.remove(&character);
+        let zero = |has_column: bool| has_column.then_some(0.0);
+
+        CharacterStats {
+            picks: row.as_ref().map_or(0, |row| row.picks),
+            pick_rate: rate(row.as_ref().map_or(0, |row| row.picks), games_played),
+            wins: row.as_ref().map_or(0, |row| row.wins),
+            win_rate: row.as_ref().map_or(0.0, |row| rate(row.wins, row.picks)),
+            average_stars: row.as_ref().map_or(zero(scoring.stars.is_some()), |row| row.average_stars),
+            average_coins: row.as_ref().map_or(zero(scoring.coins.is_some()), |row| row.average_coins),
+            character
+        }
+    }).collect();
+
+    Ok(Json(
+        CharacterStatsResponse {
+            game,
+            games_played,
+            characters
+        }
+    ))
+}
+
+// Finds one of the group's players from a head-to-head parameter, which is
+// either their id or their name. A number is tried as an id first, then as
+// a name, so players called "42" can still be found.
+async fn find_player(pool: &PgPool, group_id: i32, player: Option<&str>, param: &str) -> Result<PlayerResponse, ApiError> {
+    let player = player.map(str::trim).filter(|player| !player.is_empty()).ok_or_else(|| {
+        ApiError::BadRequest(format!("Pick a player with ?{param}="))
+    })?;
+
+    let found: Option<PlayerResponse> = sqlx::query_as("
+            SELECT id, name FROM Players
+            WHERE group_id = $1 AND (id = $2 OR lower(name) = lower($3))
+            ORDER BY (id = $2) IS TRUE DESC
+            LIMIT 1")
+        .bind(group_id)
+        .bind(player.parse::<i32>().ok())
+        .bind(player)
+        .fetch_optional(pool)
+        .await?;
+
+    found.ok_or_else(|| ApiError::NotFound(format!("There's no player called {player}")))
+}
+
+/// Every game both players were in, across every title, and how they did
+/// against each other in them. Finishing ahead is a win, whatever place it was.
+#[axum::debug_handler]
+pub async fn head_to_head(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Query(group): Query<GroupQuery>,
+    Query(params): Query<HeadToHeadQuery>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+    let group_id = member_group(&pool, user.id(), group.group).await?;
+    let scorings = scorings_for(params.game.as_deref())?;
+
+    let a = find_player(&pool, group_id, params.a.as_deref(), "a").await?;
+    let b = find_player(&pool, group_id, params.b.as_deref(), "b").await?;
+    if a.id == b.id {
+        return Err(ApiError::BadRequest(String::from("Pick two different players")));
+    }
+
+    let games: Vec<HeadToHeadGame> = sqlx::query_as(&format!("
+            WITH entries AS ({})
+            SELECT
+                a.game_id,
+                a.game,
+                a.date,
+                a.placement AS a_placement,
+                b.placement AS b_placement,
+                a.stars AS a_stars,
+                b.stars AS b_stars
+            FROM entries a
+                JOIN entries b ON b.game_id = a.game_id
+            WHERE a.player_id = $4 AND b.player_id = $5
+            ORDER BY a.date DESC, a.game_id DESC",
+            ranked_entries_query(&scorings)))
+        .bind(group_id)
+        .bind(params.from)
+        .bind(params.to)
+        .bind(a.id)
+        .bind(b.id)
+        .fetch_all(&pool)
+        .await?;
+
+    let count = |ordering: Ordering| games.iter().filter(|game| game.b_placement.cmp(&game.a_placement) == ordering).count() as i64;
+    let differentials: Vec<f64> = games.iter()
+        .filter_map(|game| Some(game.a_stars? - game.b_stars?))
+        .collect();
+    let average_star_differential = (!differentials.is_empty())
+        .then(|| differentials.iter().sum::<f64>() / differentials.len() as f64);
+
+    Ok(Json(
+        HeadToHead {
+            wins: count(Ordering::Greater),
+            losses: count(Ordering::Less),
+            ties: count(Ordering::Equal),
+            average_star_differential,
+            a,
+            b,
+            games
+        }
+    ))
+}
@@ -0,0 +1,399 @@
+use std::collections::HashSet;
+use sqlx::postgres::{PgConnection, PgPool};
+use axum_login::AuthUser;
+use axum::{
+    Extension,
+    extract::Path,
+    response::IntoResponse,
+    Json
+};
+use crate::requests::TurnSnapshot;
+use crate::responses::{PlayerTurn, TimelineResponse, TurnResponse};
+use crate::errors::ApiError;
+use crate::requests::titles;
+use crate::stats::{Scoring, SCORING};
+use crate::routes::{AuthSession, owned_game, signed_in_user};
+
+/// What's needed about a finished game to record and check its turns
+struct TimelineGame {
+    id: i32,
+    game: String,
+    turns: i32,
+    scoring: &'static Scoring,
+    // The game's space fields, the per_turn ones
+    spaces: Vec<String>,
+    entries: Vec<EntryTotals>
+}
+
+// A player's totals from their entry, what the last turn should add up to
+#[derive(sqlx::FromRow)]
+struct EntryTotals {
+    player_id: i32,
+    player_name: String,
+    stars: Option<i32>,
+    coins: Option<i32>,
+    // In the same order as TimelineGame::spaces
+    spaces: Vec<i32>
+}
+
+#[derive(sqlx::FromRow)]
+struct TurnRow {
+    turn: i32,
+    player_id: i32,
+    stars: Option<i32>,
+    coins: Option<i32>,
+    space: Option<String>
+}
+
+async fn timeline_game(conn: &mut PgConnection, game_id: i32, user_id: i32) -> Result<TimelineGame, ApiError> {
+    let not_found = || ApiError::NotFound(String::from("Game not found"));
+
+    let row: Option<(Option<String>, i32, bool)> = sqlx::query_as("
+            SELECT game, turns, draft FROM Games
+            WHERE id = $1 AND group_id IN (SELECT group_id FROM GroupMembers WHERE user_id = $2)")
+        .bind(game_id)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let (game, turns) = match row {
+        Some((_, _, true)) => {
+            return Err(ApiError::BadRequest(String::from("That game is still being played, finish it from /api/live first")));
+        },
+        Some((Some(game), turns, false)) => (game, turns),
+        _ => return Err(not_found())
+    };
+
+    let scoring = SCORING.iter().find(|scoring| scoring.game == game).ok_or_else(not_found)?;
+    let spaces: Vec<String> = titles().into_iter()
+        .find(|(name, _)| *name == game)
+        .map(|(_, fields)| fields.into_iter().filter(|field| field.per_turn).map(|field| field.name).collect())
+        .unwrap_or_default();
+
+    let column = |column: Option<&str>| column.map(|column| format!("e.{column}")).unwrap_or(String::from("NULL::int"));
+    let space_columns: Vec<String> = spaces.iter().map(|space| format!("e.{space}")).collect();
+    let entries: Vec<EntryTotals> = sqlx::query_as(&format!("
+            SELECT
+                e.player_id,
+                COALESCE(e.player_name, Players.name) AS player_name,
+                {stars} AS stars,
+                {coins} AS coins,
+                ARRAY[{spaces}]::int[] AS spaces
+            FROM {table} e
+                JOIN Players ON Players.id = e.player_id
+            WHERE e.game_id = $1
+            ORDER BY e.id",
+            stars = column(scoring.stars),
+            coins = column(scoring.coins),
+            spaces = space_columns.join(", "),
+            table = scoring.table))
+        .bind(game_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(TimelineGame {
+        id: game_id,
+        game,
+        turns,
+        scoring,
+        spaces,
+        entries
+    })
+}
+
+// Checks one snapshot against the game, and finds which player it's for
+fn snapshot_player(game: &TimelineGame, snapshot: &TurnSnapshot) -> Result<i32, String> {
+    if snapshot.turn < 1 || snapshot.turn > game.turns {
+        return Err(format!("the game only had {} turns", game.turns));
+    }
+
+    let entry = match (snapshot.player_id, snapshot.player_name.as_deref().map(str::trim)) {
+        (Some(id), _) => game.entries.iter().find(|entry| entry.player_id == id),
+        (None, Some(name)) => game.entries.iter().find(|entry| entry.player_name.eq_ignore_ascii_case(name)),
+        (None, None) => return Err(String::from("every snapshot needs a player_id or a player_name"))
+    };
+    let entry = entry.ok_or_else(|| String::from("that player isn't in this game"))?;
+
+    if snapshot.stars.is_some() && game.scoring.stars.is_none() {
+        return Err(format!("{} doesn't have stars", game.game));
+    }
+    if snapshot.coins.is_some() && game.scoring.coins.is_none() {
+        return Err(format!("{} doesn't have coins", game.game));
+    }
+    if snapshot.stars.is_some_and(|stars| stars < 0) || snapshot.coins.is_some_and(|coins| coins < 0) {
+        return Err(String::from("stars and coins can't be negative"));
+    }
+    if let Some(space) = snapshot.space.as_deref().filter(|space| !game.spaces.iter().any(|name| name == space)) {
+        return Err(format!("{} isn't a space in {}", space, game.game));
+    }
+
+    Ok(entry.player_id)
+}
+
+// Compares each player's turns with their entry
+fn problems(game: &TimelineGame, rows: &[TurnRow]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for entry in &game.entries {
+        let turns: Vec<&TurnRow> = rows.iter().filter(|row| row.player_id == entry.player_id).collect();
+        let Some(last) = turns.last() else {
+            problems.push(format!("{} doesn't have any turns", entry.player_name));
+            continue;
+        };
+
+        if last.stars.is_some() && last.stars != entry.stars {
+            problems.push(format!(
+                "{} ends turn {} with {} stars, but their entry has {}",
+                entry.player_name, last.turn, last.stars.unwrap_or(0), entry.stars.unwrap_or(0)
+            ));
+        }
+        if last.coins.is_some() && last.coins != entry.coins {
+            problems.push(format!(
+                "{} ends turn {} with {} coins, but their entry has {}",
+                entry.player_name, last.turn, last.coins.unwrap_or(0), entry.coins.unwrap_or(0)
+            ));
+        }
+
+        // Landings can only be counted up when every turn is there
+        if turns.len() as i32 == game.turns {
+            for (space, total) in game.spaces.iter().zip(&entry.spaces) {
+                let landings = turns.iter().filter(|row| row.space.as_ref() == Some(space)).count() as i32;
+                if landings != *total {
+                    problems.push(format!(
+                        "{} landed on {} {} times turn by turn, but their entry has {}",
+                        entry.player_name, space, landings, total
+                    ));
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+async fn timeline(conn: &mut PgConnection, game: &TimelineGame) -> Result<TimelineResponse, ApiError> {
+    let rows: Vec<TurnRow> = sqlx::query_as("
+            SELECT turn, player_id, stars, coins, space FROM GameTurns
+            WHERE game_id = $1
+            ORDER BY turn, id")
+        .bind(game.id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let problems = problems(game, &rows);
+
+    let mut turns: Vec<TurnResponse> = Vec::new();
+    for row in &rows {
+        let player_name = game.entries.iter()
+            .find(|entry| entry.player_id == row.player_id)
+            .map(|entry| entry.player_name.clone())
+            .unwrap_or_default();
+        let player = PlayerTurn {
+            player_id: row.player_id,
+            player_name,
+            stars: row.stars,
+            coins: row.coins,
+            space: row.space.clone()
+        };
+
+        match turns.last_mut() {
+            Some(turn) if turn.turn == row.turn => turn.players.push(player),
+            _ => turns.push(TurnResponse { turn: row.turn, players: vec![player] })
+        }
+    }
+
+    Ok(TimelineResponse {
+        game_id: game.id,
+        game: game.game.clone(),
+        turns,
+        consistent: problems.is_empty(),
+        problems
+    })
+}
+
+/// A finished game turn by turn, with a check that where everyone ended up
+/// on the last turn matches their entry
+#[axum::debug_handler]
+pub async fn game_turns(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut conn = pool.acquire().await?;
+    let game = timeline_game(&mut conn, game_id, user.id()).await?;
+
+    Ok(Json(timeline(&mut conn, &game).await?))
+}
+
+/// Replaces a finished game's turns. Turns don't have to agree with the
+/// entries to be saved, the response says where they don't.
+#[axum::debug_handler]
+pub async fn update_game_turns(
+    Extension(pool): Extension<PgPool>,
+    auth_session: AuthSession,
+    Path(game_id): Path<i32>,
+    Json(snapshots): Json<Vec<TurnSnapshot>>
+) -> Result<impl IntoResponse, ApiError> {
+    let user = signed_in_user(auth_session)?;
+
+    let mut tx = pool.begin().await?;
+    owned_game(&mut tx, game_id, user.id()).await?;
+    replace_turns(&mut tx, game_id, user.id(), snapshots).await?;
+
+    let game = timeline_game(&mut tx, game_id, user.id()).await?;
+    let timeline = timeline(&mut tx, &game).await?;
+    tx.commit().await?;
+
+    Ok(Json(timeline))
+}
+
+/// Checks each snapshot against the game and puts them in place of its
+/// turns. Restoring a backup checks its turns with this too.
+pub async fn replace_turns(conn: &mut PgConnection, game_id: i32, user_id: i32, snapshots: Vec<TurnSnapshot>) -> Result<(), ApiError> {
+    let game = timeline_game(&mut *conn, game_id, user_id).await?;
+
+    let mut seen = HashSet::new();
+    let mut turns = Vec::new();
+    let mut player_ids = Vec::new();
+    let mut stars = Vec::new();
+    let mut coins = Vec::new();
+    let mut spaces = Vec::new();
+    for snapshot in snapshots {
+        let player_id = snapshot_player(&game, &snapshot)
+            .map_err(|message| ApiError::BadRequest(format!("Turn {}: {}", snapshot.turn, message)))?;
+        if !seen.insert((snapshot.turn, player_id)) {
+            return Err(ApiError::BadRequest(format!("Turn {}: a player is in it more than once", snapshot.turn)));
+        }
+
+        turns.push(snapshot.turn);
+        player_ids.push(player_id);
+        stars.push(snapshot.stars);
+        coins.push(snapshot.coins);
+        spaces.push(snapshot.space);
+    }
+
+    sqlx::query("DELETE FROM GameTurns WHERE game_id = $1")
+        .bind(game_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("
+            INSERT INTO GameTurns (game_id, turn, player_id, stars, coins, space)
+            SELECT $1, * FROM UNNEST($2::int[], $3::int[], $4::int[], $5::int[], $6::text[])")
+        .bind(game_id)
+        .bind(turns)
+        .bind(player_ids)
+        .bind(stars)
+        .bind(coins)
+        .bind(spaces)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(player_id: i32, player_name: &str, stars: i32, coins: i32, spaces: Vec<i32>) -> EntryTotals {
+        EntryTotals { player_id, player_name: player_name.to_string(), stars: Some(stars), coins: Some(coins), spaces }
+    }
+
+    fn game(game: &str) -> TimelineGame {
+        TimelineGame {
+            id: 1,
+            game: game.to_string(),
+            turns: 3,
+            scoring: SCORING.iter().find(|scoring| scoring.game == game).unwrap(),
+            spaces: vec![String::from("blue_spaces"), String::from("red_spaces")],
+            entries: vec![entry(7, "Ann", 2, 30, vec![2, 1]), entry(8, "Bob", 1, 10, vec![3, 0])]
+        }
+    }
+
+    fn snapshot(turn: i32, player_id: Option<i32>, player_name: Option<&str>) -> TurnSnapshot {
+        TurnSnapshot { turn, player_id, player_name: player_name.map(String::from), stars: None, coins: None, space: None }
+    }
+
+    fn row(turn: i32, player_id: i32, stars: i32, coins: i32, space: &str) -> TurnRow {
+        TurnRow { turn, player_id, stars: Some(stars), coins: Some(coins), space: Some(space.to_string()) }
+    }
+
+    #[test]
+    fn snapshot_finds_the_player_by_id_or_name() {
+        let game = game("Mario Party 2");
+        assert_eq!(snapshot_player(&game, &snapshot(1, Some(8), None)), Ok(8));
+        assert_eq!(snapshot_player(&game, &snapshot(1, None, Some(" ann "))), Ok(7));
+        assert_eq!(snapshot_player(&game, &snapshot(1, Some(9), None)), Err(String::from("that player isn't in this game")));
+        assert!(snapshot_player(&game, &snapshot(1, None, None)).is_err());
+    }
+
+    #[test]
+    fn snapshot_turn_has_to_be_in_the_game() {
+        let game = game("Mario Party 2");
+        assert!(snapshot_player(&game, &snapshot(3, Some(7), None)).is_ok());
+        assert_eq!(snapshot_player(&game, &snapshot(4, Some(7), None)), Err(String::from("the game only had 3 turns")));
+        assert!(snapshot_player(&game, &snapshot(0, Some(7), None)).is_err());
+    }
+
+    #[test]
+    fn snapshot_values_have_to_fit_the_game() {
+        let mut snapshot = snapshot(1, Some(7), None);
+        snapshot.stars = Some(1);
+        assert_eq!(
+            snapshot_player(&game("Mario Party: Island Tour"), &snapshot),
+            Err(String::from("Mario Party: Island Tour doesn't have stars"))
+        );
+
+        snapshot.stars = Some(-1);
+        assert!(snapshot_player(&game("Mario Party 2"), &snapshot).is_err());
+
+        snapshot.stars = None;
+        snapshot.space = Some(String::from("bowser_spaces"));
+        assert_eq!(
+            snapshot_player(&game("Mario Party 2"), &snapshot),
+            Err(String::from("bowser_spaces isn't a space in Mario Party 2"))
+        );
+
+        snapshot.space = Some(String::from("red_spaces"));
+        assert_eq!(snapshot_player(&game("Mario Party 2"), &snapshot), Ok(7));
+    }
+
+    #[test]
+    fn no_problems_when_the_turns_add_up() {
+        let rows = [
+            row(1, 7, 0, 13, "blue_spaces"),
+            row(1, 8, 0, 13, "blue_spaces"),
+            row(2, 7, 1, 20, "red_spaces"),
+            row(2, 8, 1, 5, "blue_spaces"),
+            row(3, 7, 2, 30, "blue_spaces"),
+            row(3, 8, 1, 10, "blue_spaces")
+        ];
+        assert!(problems(&game("Mario Party 2"), &rows).is_empty());
+    }
+
+    #[test]
+    fn problems_with_the_last_turn() {
+        let rows = [row(1, 7, 0, 13, "blue_spaces"), row(2, 7, 3, 25, "blue_spaces")];
+        assert_eq!(problems(&game("Mario Party 2"), &rows), vec![
+            String::from("Ann ends turn 2 with 3 stars, but their entry has 2"),
+            String::from("Ann ends turn 2 with 25 coins, but their entry has 30"),
+            String::from("Bob doesn't have any turns")
+        ]);
+    }
+
+    #[test]
+    fn landings_are_only_counted_with_every_turn() {
+        let rows = [
+            row(1, 7, 0, 13, "blue_spaces"),
+            row(2, 7, 1, 20, "blue_spaces"),
+            row(3, 7, 2, 30, "blue_spaces"),
+            row(1, 8, 0, 13, "blue_spaces"),
+            row(3, 8, 1, 10, "blue_spaces")
+        ];
+        assert_eq!(problems(&game("Mario Party 2"), &rows), vec![
+            String::from("Ann landed on blue_spaces 3 times turn by turn, but their entry has 2"),
+            String::from("Ann landed on red_spaces 0 times turn by turn, but their entry has 1")
+        ]);
+    }
+}